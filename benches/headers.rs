@@ -1,6 +1,9 @@
+// The naive variants are deliberately written the obvious way for comparison.
+#![allow(clippy::manual_try_fold, clippy::result_unit_err)]
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use headers::{CacheControl, Header, HeaderMap, HeaderMapExt, HeaderName, HeaderValue};
+use headers_03::{HeaderMap, HeaderMapExt, HeaderValue};
 
 use http_header_types::cache_control;
 
@@ -67,14 +70,17 @@ fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| naive(black_box(b"0002342342")))
     });
     c.bench_function("atoi", |b| b.iter(|| atoi_test(black_box(b"0002342342"))));
-    // let mut headers = HeaderMap::new();
-    // headers.insert(
-    //     "Cache-Control",
-    //     HeaderValue::from_static("no-cache, max-age=100"),
-    // );
-    // c.bench_function("cache-control-headers", |b| {
-    //     b.iter(|| black_box(&headers).typed_get::<CacheControl>())
-    // });
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Cache-Control",
+        HeaderValue::from_static("no-cache, max-age=100"),
+    );
+    c.bench_function("cache-control-headers", |b| {
+        b.iter(|| black_box(&headers).typed_get::<headers_03::CacheControl>())
+    });
+    c.bench_function("cache-control", |b| {
+        b.iter(|| black_box(&headers).typed_get::<cache_control::CacheControl>())
+    });
     // c.bench_function("cache-control-nom", |b| {
     //     b.iter(|| {
     //         let _ = cache_control(black_box(&headers).get("Cache-Control").unwrap().as_bytes());
@@ -0,0 +1,25 @@
+#![no_main]
+
+use http_header_types::age::Age;
+use http_header_types::diagnostics::Diagnostics;
+use http_header_types::mode::ParseMode;
+use http_header_types_fuzz::{bounded_alloc, field_lines, is_field_value, roundtrip};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let lines = field_lines(data);
+    for mode in [ParseMode::Strict, ParseMode::Lenient, ParseMode::Compat] {
+        let mut diagnostics = Diagnostics::new();
+        let age = bounded_alloc(data.len(), || {
+            Age::decode_with(&mut lines.iter(), mode, &mut diagnostics)
+        });
+        if let Ok(age) = age {
+            roundtrip(&age, Age::to_field_lines, Age::parse_field_lines);
+        }
+    }
+    if lines.iter().all(|l| is_field_value(l)) {
+        if let Ok(age) = Age::parse_field_lines(&lines) {
+            assert!(!age.to_string().is_empty());
+        }
+    }
+});
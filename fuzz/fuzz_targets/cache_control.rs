@@ -0,0 +1,60 @@
+#![no_main]
+
+use http_header_types::cache_control::{
+    CacheControl, CacheControlRef, RawCacheControl, RequestCacheControl, ResponseCacheControl,
+};
+use http_header_types::diagnostics::Diagnostics;
+use http_header_types::mode::ParseMode;
+use http_header_types_fuzz::{bounded_alloc, field_lines, is_field_value, or_default, roundtrip};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let lines = field_lines(data);
+    let received = lines.iter().all(|l| is_field_value(l));
+    for mode in [ParseMode::Strict, ParseMode::Lenient, ParseMode::Compat] {
+        let mut diagnostics = Diagnostics::new();
+        let cc = bounded_alloc(data.len(), || {
+            CacheControl::decode_with(&mut lines.iter(), mode, &mut diagnostics)
+        });
+        let cc_ref = bounded_alloc(data.len(), || {
+            CacheControlRef::decode_with(&mut lines.iter(), mode, &mut ())
+        });
+        assert_eq!(cc_ref.map(CacheControlRef::into_owned), cc);
+        if let (Ok(cc), true) = (cc, received) {
+            roundtrip(
+                &cc,
+                CacheControl::to_field_lines,
+                or_default(CacheControl::parse_field_lines),
+            );
+        }
+
+        let request = bounded_alloc(data.len(), || {
+            RequestCacheControl::decode_with(&mut lines.iter(), mode, &mut ())
+        });
+        if let (Ok(request), true) = (request, received) {
+            roundtrip(
+                &request,
+                RequestCacheControl::to_field_lines,
+                or_default(RequestCacheControl::parse_field_lines),
+            );
+        }
+        let response = bounded_alloc(data.len(), || {
+            ResponseCacheControl::decode_with(&mut lines.iter(), mode, &mut ())
+        });
+        if let (Ok(response), true) = (response, received) {
+            roundtrip(
+                &response,
+                ResponseCacheControl::to_field_lines,
+                or_default(ResponseCacheControl::parse_field_lines),
+            );
+        }
+    }
+
+    let raw = bounded_alloc(data.len(), || RawCacheControl::parse_field_lines(&lines));
+    if let (Ok(raw), true) = (raw, received) {
+        // Unmodified directives are forwarded as received.
+        let encoded = raw.to_field_lines();
+        assert_eq!(encoded.iter().map(|l| &l[..]).collect::<Vec<_>>(), lines);
+        let _ = raw.cache_control(ParseMode::Lenient, &mut ());
+    }
+});
@@ -0,0 +1,34 @@
+#![no_main]
+
+use http_header_types::diagnostics::Diagnostics;
+use http_header_types::expires::{Expires, LinePolicy};
+use http_header_types::mode::ParseMode;
+use http_header_types_fuzz::{bounded_alloc, field_lines, is_field_value, roundtrip};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let lines = field_lines(data);
+    let received = lines.iter().all(|l| is_field_value(l));
+    for mode in [ParseMode::Strict, ParseMode::Lenient, ParseMode::Compat] {
+        for policy in [
+            LinePolicy::FirstValid,
+            LinePolicy::First,
+            LinePolicy::Earliest,
+        ] {
+            let mut diagnostics = Diagnostics::new();
+            let expires = bounded_alloc(data.len(), || {
+                Expires::decode_with_policy(&mut lines.iter(), mode, policy, &mut diagnostics)
+            });
+            match expires {
+                Ok(expires) if received => roundtrip(
+                    &expires,
+                    Expires::to_field_lines,
+                    Expires::parse_field_lines,
+                ),
+                // Only a missing value is rejected, unless parsing strictly.
+                Err(_) => assert!(mode == ParseMode::Strict || lines.is_empty()),
+                Ok(_) => {}
+            }
+        }
+    }
+});
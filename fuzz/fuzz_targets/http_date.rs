@@ -0,0 +1,31 @@
+#![no_main]
+
+use http_header_types::date::{parse_http_date_with, DateFormat, HttpDate};
+use http_header_types::mode::ParseMode;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The first two bytes pick the year two-digit years are resolved against.
+    let (reference_year, data) = match data {
+        [a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]), rest),
+        _ => return,
+    };
+    let mut accepted = None;
+    for mode in [ParseMode::Strict, ParseMode::Lenient, ParseMode::Compat] {
+        let parsed = parse_http_date_with(data, mode, reference_year).ok();
+        // Each mode accepts everything the stricter ones do, with the same
+        // meaning.
+        if accepted.is_some() {
+            assert_eq!(parsed, accepted, "{:?}", mode);
+        }
+        accepted = parsed;
+        if let Some((date, _)) = parsed {
+            let imf_fixdate = date.to_imf_fixdate();
+            assert_eq!(
+                parse_http_date_with(&imf_fixdate, ParseMode::Strict, reference_year),
+                Ok((date, DateFormat::ImfFixdate))
+            );
+            assert_eq!(HttpDate::from_unix_secs(date.unix_secs()), Some(date));
+        }
+    }
+});
@@ -0,0 +1,57 @@
+#![no_main]
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use http_header_types::delta_seconds::DeltaSeconds;
+use http_header_types::error::{ParseError, ParseErrorKind};
+use http_header_types::util::{parse_u16, parse_u32, parse_u64, parse_u8};
+use libfuzzer_sys::fuzz_target;
+
+/// Compares with the standard library, which also accepts a leading `+`.
+fn check<T>(
+    data: &[u8],
+    parse: fn(&[u8]) -> Result<T, ParseError>,
+    parse_saturating: fn(&[u8]) -> Result<T, ParseError>,
+    max: T,
+) where
+    T: FromStr + Debug + PartialEq + Copy,
+{
+    let is_digits = !data.is_empty() && data.iter().all(u8::is_ascii_digit);
+    let ret = parse(data).map_err(|e| e.kind());
+    let saturated = parse_saturating(data).map_err(|e| e.kind());
+    if !is_digits {
+        assert_eq!(ret, Err(ParseErrorKind::InvalidInteger));
+        assert_eq!(saturated, Err(ParseErrorKind::InvalidInteger));
+        return;
+    }
+    let digits = std::str::from_utf8(data).unwrap();
+    match digits.parse::<T>() {
+        Ok(v) => {
+            assert_eq!(ret, Ok(v));
+            assert_eq!(saturated, Ok(v));
+        }
+        Err(_) => {
+            assert_eq!(ret, Err(ParseErrorKind::IntegerOverflow));
+            assert_eq!(saturated, Ok(max));
+            // Reported at the first digit the type cannot hold.
+            let len = (1..=digits.len())
+                .find(|&len| digits[..len].parse::<T>().is_err())
+                .unwrap();
+            assert_eq!(parse(data).unwrap_err().offset(), len - 1);
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    check(data, parse_u8::<false>, parse_u8::<true>, u8::MAX);
+    check(data, parse_u16::<false>, parse_u16::<true>, u16::MAX);
+    check(data, parse_u32::<false>, parse_u32::<true>, u32::MAX);
+    check(data, parse_u64::<false>, parse_u64::<true>, u64::MAX);
+
+    let delta = DeltaSeconds::parse(data);
+    assert_eq!(delta, parse_u64::<true>(data).map(DeltaSeconds::new));
+    if let Ok(delta) = delta {
+        assert_eq!(DeltaSeconds::parse(delta.to_string().as_bytes()), Ok(delta));
+    }
+});
@@ -0,0 +1,52 @@
+#![no_main]
+
+use http_header_types::age::Age;
+use http_header_types::cache_control::{
+    CacheControl, RawCacheControl, RequestCacheControl, ResponseCacheControl,
+};
+use http_header_types::expires::Expires;
+use http_header_types_fuzz::{or_default, roundtrip};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (
+    CacheControl,
+    RequestCacheControl,
+    ResponseCacheControl,
+    RawCacheControl,
+    Age,
+    Expires,
+)| {
+    let (cc, request, response, raw, age, expires) = input;
+    roundtrip(
+        &cc,
+        CacheControl::to_field_lines,
+        or_default(CacheControl::parse_field_lines),
+    );
+    roundtrip(
+        &request,
+        RequestCacheControl::to_field_lines,
+        or_default(RequestCacheControl::parse_field_lines),
+    );
+    roundtrip(
+        &response,
+        ResponseCacheControl::to_field_lines,
+        or_default(ResponseCacheControl::parse_field_lines),
+    );
+    // A modified `RawCacheControl` is encoded in canonical form, which keeps
+    // its directives; an unmodified one is encoded byte for byte.
+    let decode_raw = or_default(RawCacheControl::parse_field_lines);
+    roundtrip(
+        &raw.directives().to_vec(),
+        |_| raw.to_field_lines(),
+        |lines| decode_raw(lines).map(|raw| raw.directives().to_vec()),
+    );
+    if !raw.is_modified() {
+        roundtrip(&raw, RawCacheControl::to_field_lines, &decode_raw);
+    }
+    roundtrip(&age, Age::to_field_lines, Age::parse_field_lines);
+    roundtrip(
+        &expires,
+        Expires::to_field_lines,
+        Expires::parse_field_lines,
+    );
+});
@@ -0,0 +1,123 @@
+//! Checks shared by the fuzz targets.
+//!
+//! Run a target with `cargo fuzz run <target>` from the repository root.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use http_header_types::error::ParseError;
+
+/// Most bytes a decoder may have allocated at once per byte of input, on top
+/// of [`ALLOC_BASE`]. Any linear bound will do; what matters is that no input
+/// makes a decoder allocate without bound.
+pub const ALLOC_PER_BYTE: usize = 256;
+pub const ALLOC_BASE: usize = 64 * 1024;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Keeps track of the bytes allocated, for [`bounded_alloc`].
+struct CountingAllocator;
+
+fn allocated(size: usize) {
+    let now = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ret = System.alloc(layout);
+        if !ret.is_null() {
+            allocated(layout.size());
+        }
+        ret
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ret = System.alloc_zeroed(layout);
+        if !ret.is_null() {
+            allocated(layout.size());
+        }
+        ret
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let ret = System.realloc(ptr, layout, new_size);
+        if !ret.is_null() {
+            CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        ret
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, asserting that it never has more than allowed for `input_len`
+/// bytes of input allocated at once, including what it returns.
+pub fn bounded_alloc<T>(input_len: usize, f: impl FnOnce() -> T) -> T {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let ret = f();
+    let used = PEAK.load(Ordering::Relaxed).saturating_sub(base);
+    let limit = ALLOC_BASE + ALLOC_PER_BYTE * input_len;
+    assert!(
+        used <= limit,
+        "allocated {} bytes decoding {} bytes",
+        used,
+        input_len
+    );
+    ret
+}
+
+/// Splits fuzzer input into field lines at `\n`.
+pub fn field_lines(data: &[u8]) -> Vec<&[u8]> {
+    data.split(|&c| c == b'\n').collect()
+}
+
+/// Whether `line` could have been received, i.e. is a valid
+/// `http::HeaderValue`.
+pub fn is_field_value(line: &[u8]) -> bool {
+    http::HeaderValue::from_bytes(line).is_ok()
+}
+
+/// Checks that `value` encodes to valid field values, which decode to
+/// `value` again.
+pub fn roundtrip<T, L>(
+    value: &T,
+    encode: impl Fn(&T) -> Vec<L>,
+    decode: impl Fn(&[&[u8]]) -> Result<T, ParseError>,
+) where
+    T: Debug + PartialEq,
+    L: AsRef<[u8]>,
+{
+    let lines = encode(value);
+    let lines: Vec<&[u8]> = lines.iter().map(AsRef::as_ref).collect();
+    for line in &lines {
+        assert!(
+            is_field_value(line),
+            "{:?} encodes to invalid field value {:?}",
+            value,
+            String::from_utf8_lossy(line)
+        );
+    }
+    assert_eq!(decode(&lines).as_ref(), Ok(value), "encoded as {:?}", lines);
+}
+
+/// Decodes with `decode`, except that no field lines are no directives, which
+/// is how an empty Cache-Control is encoded.
+pub fn or_default<T: Default>(
+    decode: impl Fn(&[&[u8]]) -> Result<T, ParseError>,
+) -> impl Fn(&[&[u8]]) -> Result<T, ParseError> {
+    move |lines| match lines {
+        [] => Ok(T::default()),
+        _ => decode(lines),
+    }
+}
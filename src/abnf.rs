@@ -0,0 +1,347 @@
+//! Core rules of the HTTP field value grammar
+//!
+//! Character classes and [`nom`] recognizers for the rules of RFC9110 that
+//! most field values are built from. Recognizers return the matched input
+//! without copying it; [`unquote`] decodes a quoted-string when its content
+//! is needed.
+//!
+//! ```
+//! use http_header_types::abnf::{list, parameters, token};
+//! use nom::sequence::pair;
+//!
+//! let value = b"gzip;q=1.0, br , ,identity;q=\"0\"";
+//! let mut accept_encoding = list(pair(token, parameters));
+//! assert_eq!(accept_encoding(value), Ok((&b""[..], &value[..])));
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::combinator::{opt, recognize};
+use nom::multi::many0_count;
+use nom::sequence::{delimited, pair, preceded, tuple};
+use nom::{IResult, Parser};
+
+use crate::delta_seconds::DeltaSeconds;
+
+// ### RFC5234 B.1. Core Rules
+//
+// ```
+//   DIGIT          =  %x30-39
+//   HTAB           =  %x09
+//   SP             =  %x20
+//   VCHAR          =  %x21-7E
+//   WSP            =  SP / HTAB
+// ```
+
+pub fn is_digit(chr: u8) -> bool {
+    chr.is_ascii_digit()
+}
+
+pub fn is_vchar(chr: u8) -> bool {
+    matches!(chr, 0x21..=0x7e)
+}
+
+pub fn is_wsp(chr: u8) -> bool {
+    matches!(chr, b' ' | b'\t')
+}
+
+// ### RFC9110 5.5. Field Values
+//
+// ```
+//   obs-text       = %x80-FF
+// ```
+
+pub fn is_obs_text(chr: u8) -> bool {
+    matches!(chr, 0x80..=0xff)
+}
+
+// ### RFC9110 5.6.2. Tokens
+//
+// ```
+//   token          = 1*tchar
+//
+//   tchar          = "!" / "#" / "$" / "%" / "&" / "'" / "*"
+//                  / "+" / "-" / "." / "^" / "_" / "`" / "|" / "~"
+//                  / DIGIT / ALPHA
+//                  ; any VCHAR, except delimiters
+// ```
+
+#[rustfmt::skip]
+pub fn is_tchar(chr: u8) -> bool {
+    matches!(chr, b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' |
+                  b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' |
+                  b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z')
+}
+
+pub fn token(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(is_tchar)(i)
+}
+
+// ### RFC9110 5.6.3. Whitespace
+//
+// ```
+//   OWS            = *( SP / HTAB )
+//                  ; optional whitespace
+//   RWS            = 1*( SP / HTAB )
+//                  ; required whitespace
+//   BWS            = OWS
+//                  ; "bad" whitespace
+// ```
+
+pub fn ows(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while(is_wsp)(i)
+}
+
+pub fn rws(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(is_wsp)(i)
+}
+
+pub fn bws(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    ows(i)
+}
+
+// ### RFC9110 5.6.4. Quoted Strings
+//
+// ```
+//   quoted-string  = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+//   qdtext         = HTAB / SP / %x21 / %x23-5B / %x5D-7E / obs-text
+//   quoted-pair    = "\" ( HTAB / SP / VCHAR / obs-text )
+// ```
+
+pub fn is_qdtext(chr: u8) -> bool {
+    matches!(chr, b'\t' | b' ' | 0x21 | 0x23..=0x5b | 0x5d..=0x7e | 0x80..=0xff)
+}
+
+/// Whether `chr` can follow the backslash of a quoted-pair.
+pub fn is_quoted_pair_char(chr: u8) -> bool {
+    is_wsp(chr) || is_vchar(chr) || is_obs_text(chr)
+}
+
+pub fn quoted_pair(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(pair(
+        tag(b"\\"),
+        nom::bytes::complete::take_while_m_n(1, 1, is_quoted_pair_char),
+    ))(i)
+}
+
+/// Recognizes a quoted-string, including its quotes.
+pub fn quoted_string(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(delimited(
+        tag(b"\""),
+        many0_count(alt((take_while1(is_qdtext), quoted_pair))),
+        tag(b"\""),
+    ))(i)
+}
+
+/// The content of a quoted-string, with the quotes and quoted-pairs removed.
+/// Anything else is returned as is, so this also works for values that can
+/// be either a token or a quoted-string.
+pub fn unquote(v: &[u8]) -> Cow<'_, [u8]> {
+    let inner = match v {
+        [b'"', inner @ .., b'"'] => inner,
+        _ => return Cow::Borrowed(v),
+    };
+    if !inner.contains(&b'\\') {
+        return Cow::Borrowed(inner);
+    }
+    let mut ret = Vec::with_capacity(inner.len());
+    let mut escaped = false;
+    for &c in inner {
+        if c == b'\\' && !escaped {
+            escaped = true;
+        } else {
+            ret.push(c);
+            escaped = false;
+        }
+    }
+    Cow::Owned(ret)
+}
+
+// ### RFC9110 5.6.5. Comments
+//
+// ```
+//   comment        = "(" *( ctext / quoted-pair / comment ) ")"
+//   ctext          = HTAB / SP / %x21-27 / %x2A-5B / %x5D-7E / obs-text
+// ```
+
+pub fn is_ctext(chr: u8) -> bool {
+    matches!(chr, b'\t' | b' ' | 0x21..=0x27 | 0x2a..=0x5b | 0x5d..=0x7e | 0x80..=0xff)
+}
+
+/// Recognizes a comment, including its parentheses and nested comments.
+pub fn comment(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(delimited(
+        tag(b"("),
+        many0_count(alt((take_while1(is_ctext), quoted_pair, comment))),
+        tag(b")"),
+    ))(i)
+}
+
+// ### RFC9110 5.6.1.2. Recipient Requirements
+//
+// Empty elements do not contribute to the count of elements present. A
+// recipient MUST parse and ignore a reasonable number of empty list elements:
+// enough to handle common mistakes by senders that merge values, but not so
+// much that they could be used as a denial-of-service mechanism. In other
+// words, a recipient MUST accept lists that satisfy the following syntax:
+//
+// ```
+//   #element => [ element ] *( OWS "," OWS [ element ] )
+// ```
+
+/// Recognizes a `#element` list, with surrounding whitespace and empty
+/// elements, as accepted by a recipient.
+pub fn list<'a, O, F>(mut element: F) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], &'a [u8]>
+where
+    F: Parser<&'a [u8], O, nom::error::Error<&'a [u8]>>,
+{
+    move |i| list_of(i, &mut element, false)
+}
+
+/// Like [`list`], but requires at least one non-empty element, as for
+/// `1#element`.
+pub fn list1<'a, O, F>(mut element: F) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], &'a [u8]>
+where
+    F: Parser<&'a [u8], O, nom::error::Error<&'a [u8]>>,
+{
+    move |i| list_of(i, &mut element, true)
+}
+
+fn list_of<'a, O, F>(i: &'a [u8], element: &mut F, required: bool) -> IResult<&'a [u8], &'a [u8]>
+where
+    F: Parser<&'a [u8], O, nom::error::Error<&'a [u8]>>,
+{
+    let (mut rest, _) = ows(i)?;
+    let mut found = false;
+    loop {
+        match element.parse(rest) {
+            Ok((r, _)) => {
+                rest = r;
+                found = true;
+            }
+            Err(nom::Err::Error(_)) => {}
+            Err(e) => return Err(e),
+        }
+        match tuple((ows, tag(b","), ows))(rest) {
+            Ok((r, _)) => rest = r,
+            Err(_) => break,
+        }
+    }
+    if required && !found {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            rest,
+            nom::error::ErrorKind::SeparatedList,
+        )));
+    }
+    let (rest, _) = ows(rest)?;
+    Ok((rest, &i[..i.len() - rest.len()]))
+}
+
+// ### RFC9110 5.6.6. Parameters
+//
+// ```
+//   parameters      = *( OWS ";" OWS [ parameter ] )
+//   parameter       = parameter-name "=" parameter-value
+//   parameter-name  = token
+//   parameter-value = ( token / quoted-string )
+// ```
+
+/// A token or a quoted-string, including its quotes; see [`unquote`].
+pub fn parameter_value(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((token, quoted_string))(i)
+}
+
+/// A `name=value` parameter, returning the name and the undecoded value.
+pub fn parameter(i: &[u8]) -> IResult<&[u8], (&[u8], &[u8])> {
+    pair(token, preceded(tag(b"="), parameter_value))(i)
+}
+
+/// Recognizes any number of `;name=value` parameters.
+pub fn parameters(i: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(many0_count(tuple((ows, tag(b";"), ows, opt(parameter)))))(i)
+}
+
+// ### RFC9111 1.2.2. Delta Seconds
+//
+// ```
+//   delta-seconds  = 1*DIGIT
+// ```
+//
+// If a cache receives a delta-seconds value greater than the greatest integer
+// it can represent, or if any of its subsequent calculations overflows, the
+// cache MUST consider the value to be 2147483648 (2^31) or the greatest
+// positive integer it can conveniently represent.
+
+/// Parses delta-seconds, saturating at [`DeltaSeconds::OVERFLOW`].
+pub fn delta_seconds(i: &[u8]) -> IResult<&[u8], DeltaSeconds> {
+    take_while1(is_digit)
+        .map(|digits| DeltaSeconds::parse(digits).unwrap())
+        .parse(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok<O>(rest: &[u8], o: O) -> IResult<&[u8], O> {
+        Ok((rest, o))
+    }
+
+    #[test]
+    fn test_tokens_and_strings() {
+        assert_eq!(token(b"max-age=5"), ok(b"=5", &b"max-age"[..]));
+        assert!(token(b"=5").is_err());
+        assert_eq!(
+            quoted_string(b"\"a\\\"b\", c"),
+            ok(b", c", &b"\"a\\\"b\""[..])
+        );
+        assert_eq!(quoted_string(b"\"\""), ok(b"", &b"\"\""[..]));
+        assert!(quoted_string(b"\"abc").is_err());
+        assert!(quoted_string(b"\"a\rb\"").is_err());
+        assert_eq!(unquote(b"\"a\\\"b\\\\\""), &b"a\"b\\"[..]);
+        assert!(matches!(unquote(b"\"ab\""), Cow::Borrowed(b"ab")));
+        assert_eq!(unquote(b"ab"), &b"ab"[..]);
+        assert_eq!(
+            comment(b"(a (nested \\) comment)) x"),
+            ok(b" x", &b"(a (nested \\) comment))"[..])
+        );
+        assert!(comment(b"(a (b)").is_err());
+        assert_eq!(ows(b" \tx"), ok(b"x", &b" \t"[..]));
+        assert!(rws(b"x").is_err());
+        assert_eq!(bws(b"x"), ok(b"x", &b""[..]));
+        assert_eq!(delta_seconds(b"0120,"), ok(b",", DeltaSeconds::new(120)));
+        assert_eq!(
+            delta_seconds(b"2147483649"),
+            ok(b"", DeltaSeconds::OVERFLOW)
+        );
+        assert_eq!(
+            delta_seconds(b"99999999999999999999999"),
+            ok(b"", DeltaSeconds::OVERFLOW)
+        );
+        assert!(delta_seconds(b"-1").is_err());
+    }
+
+    #[test]
+    fn test_lists_and_parameters() {
+        assert_eq!(
+            parameter(b"q=\"0.5\";"),
+            ok(b";", (&b"q"[..], &b"\"0.5\""[..]))
+        );
+        assert_eq!(
+            parameters(b" ; a=1;b=\"x y\" ;, z"),
+            ok(b", z", &b" ; a=1;b=\"x y\" ;"[..])
+        );
+
+        let mut tokens = list(token);
+        assert_eq!(tokens(b" , a ,, b , "), ok(b"", &b" , a ,, b , "[..]));
+        assert_eq!(tokens(b""), ok(b"", &b""[..]));
+        assert_eq!(tokens(b"a b"), ok(b"b", &b"a "[..]));
+
+        let mut tokens = list1(token);
+        assert_eq!(tokens(b", a"), ok(b"", &b", a"[..]));
+        assert!(tokens(b" , ,").is_err());
+    }
+}
@@ -11,7 +11,7 @@
 //! ```
 //!
 //! The Age field value is a non-negative integer, representing time in seconds
-//! (see Section 1.2.2).
+//! (see Section 1.2.2 and [`DeltaSeconds`]).
 //!
 //! Although it is defined as a singleton header field, a cache encountering a
 //! message with a list-based Age field value SHOULD use the first member of the
@@ -25,134 +25,331 @@
 //! generated or validated by the origin server for this request. However, lack
 //! of an Age header field does not imply the origin was contacted.
 //!
-//! ### RFC9111 1.2.2. Delta Seconds
+//! ### RFC9111 4.2.3. Calculating Age
 //!
-//! The delta-seconds rule specifies a non-negative integer, representing time
-//! in seconds.
+//! The following data is used for the age calculation:
+//!
+//! - age_value: The term "age_value" denotes the value of the Age header
+//!   field (Section 5.1), in a form appropriate for arithmetic operation; or
+//!   0, if not available.
+//! - date_value: The term "date_value" denotes the value of the Date header
+//!   field, in a form appropriate for arithmetic operations.
+//! - now: The term "now" means the current value of this implementation's
+//!   clock.
+//! - request_time: The value of the clock at the time of the request that
+//!   resulted in the stored response.
+//! - response_time: The value of the clock at the time the response was
+//!   received.
+//!
+//! A response's age can be calculated in two entirely independent ways:
+//!
+//! 1. the "apparent_age": response_time minus date_value, if the implementation's
+//!    clock is reasonably well synchronized to the origin server's clock. If
+//!    the result is negative, the result is replaced by zero.
+//! 2. the "corrected_age_value", if all of the caches along the response path
+//!    implement HTTP/1.1 or greater. A cache MUST interpret this value
+//!    relative to the time the request was initiated, not the time that the
+//!    response was received.
 //!
 //! ```text
-//! delta-seconds  = 1*DIGIT
+//!   apparent_age = max(0, response_time - date_value);
+//!
+//!   response_delay = response_time - request_time;
+//!   corrected_age_value = age_value + response_delay;
 //! ```
 //!
-//! A recipient parsing a delta-seconds value and converting it to binary form
-//! ought to use an arithmetic type of at least 31 bits of non-negative integer
-//! range. If a cache receives a delta-seconds value greater than the greatest
-//! integer it can represent, or if any of its subsequent calculations
-//! overflows, the cache MUST consider the value to be 2147483648 (231) or the
-//! greatest positive integer it can conveniently represent.
-//!
-//! Note: The value 2147483648 is here for historical reasons, represents
-//! infinity (over 68 years), and does not need to be stored in binary form; an
-//! implementation could produce it as a string if any overflow occurs, even if
-//! the calculations are performed with an arithmetic type incapable of directly
-//! representing that number. What matters here is that an overflow be detected
-//! and not treated as a negative value in later calculations.
-
-use headers::{Header, HeaderName, HeaderValue};
-use logos::Logos;
-
-use crate::util::parse_u64;
-
-#[derive(Clone, Debug, PartialEq)]
-struct Age(u64);
-
-#[derive(Logos, Debug, PartialEq)]
-#[logos(skip r"[ \t]+")]
-enum AgeToken {
-    #[regex(b"[0-9]+")]
-    Int,
-    #[token(b",")]
-    Comma,
+//! The corrected_age_value MAY be used as the corrected_initial_age. In
+//! circumstances where very old cache implementations that might not
+//! correctly insert Age are present, corrected_initial_age can be calculated
+//! more conservatively as
+//!
+//! ```text
+//!   corrected_initial_age = max(apparent_age, corrected_age_value);
+//! ```
+//!
+//! The current_age of a stored response can then be calculated by adding the
+//! time (in seconds) since the stored response was last validated by the
+//! origin server to the corrected_initial_age.
+//!
+//! ```text
+//!   resident_time = now - response_time;
+//!   current_age = corrected_initial_age + resident_time;
+//! ```
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+use bytes::Bytes;
+
+use crate::abnf::is_wsp;
+#[cfg(feature = "std")]
+use crate::date::HttpDate;
+use crate::delta_seconds::{process_delta_seconds, DeltaSeconds};
+use crate::diagnostics::{Context, DiagnosticKind, DiagnosticSink};
+use crate::error::{ParseError, ParseErrorKind};
+#[cfg(feature = "std")]
+use crate::fields::Fields;
+use crate::list::{Element, ListIter};
+use crate::mode::ParseMode;
+use crate::ser;
+use crate::util::leading_digits;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Age(DeltaSeconds);
+
+impl Age {
+    /// See [`DeltaSeconds::OVERFLOW`].
+    pub const OVERFLOW: Age = Age(DeltaSeconds::OVERFLOW);
+
+    /// Saturates at [`Age::OVERFLOW`].
+    pub fn new(seconds: u64) -> Self {
+        Age(DeltaSeconds::new(seconds))
+    }
+
+    pub fn seconds(&self) -> u64 {
+        self.0.seconds()
+    }
+
+    pub fn delta_seconds(&self) -> DeltaSeconds {
+        self.0
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.0.as_duration()
+    }
 }
 
-fn process_header(i: &[u8]) -> Result<Option<Age>, ()> {
-    let mut l = AgeToken::lexer(i);
-    let mut ret = None;
-    while let Some(t) = l.next() {
-        match t? {
-            AgeToken::Comma if ret.is_some() => return Ok(ret),
-            AgeToken::Comma => {}
-            AgeToken::Int if ret.is_none() => {
-                ret = Some(Age(parse_u64::<true>(l.slice())?));
-            }
-            AgeToken::Int => return Err(()),
-        };
+#[cfg(feature = "std")]
+/// Seconds from `earlier` to `later`, or zero if `later` is not later.
+fn seconds_between(earlier: SystemTime, later: SystemTime) -> u64 {
+    later.duration_since(earlier).map_or(0, |d| d.as_secs())
+}
+
+#[cfg(feature = "std")]
+/// The current age of a stored response, calculated as in Section 4.2.3.
+///
+/// `age_value` is the Age of the stored response, if it had one. If any step
+/// overflows, or the result exceeds it, the result is [`Age::OVERFLOW`].
+pub fn current_age(
+    age_value: Option<Age>,
+    date_value: SystemTime,
+    request_time: SystemTime,
+    response_time: SystemTime,
+    now: SystemTime,
+) -> Age {
+    let apparent_age = seconds_between(date_value, response_time);
+    let response_delay = seconds_between(request_time, response_time);
+    let resident_time = seconds_between(response_time, now);
+    age_value
+        .map_or(0, |a| a.seconds())
+        .checked_add(response_delay)
+        .map(|corrected_age_value| corrected_age_value.max(apparent_age))
+        .and_then(|corrected_initial_age| corrected_initial_age.checked_add(resident_time))
+        .map_or(Age::OVERFLOW, Age::new)
+}
+
+#[cfg(feature = "std")]
+impl Age {
+    /// The Age to send when serving a stored response, given the headers it
+    /// was stored with.
+    ///
+    /// A missing or invalid Date is taken to be `response_time`, as a
+    /// recipient would have done on receiving it.
+    pub fn for_stored_response(
+        stored: &(impl Fields + ?Sized),
+        request_time: SystemTime,
+        response_time: SystemTime,
+        now: SystemTime,
+    ) -> Age {
+        let date_value = stored
+            .values("date")
+            .next()
+            .and_then(|v| HttpDate::parse(v).ok())
+            .map(SystemTime::from)
+            .unwrap_or(response_time);
+        current_age(
+            Age::try_decode(&mut stored.values("age")).ok(),
+            date_value,
+            request_time,
+            response_time,
+            now,
+        )
     }
-    Ok(ret)
 }
 
-impl Header for Age {
-    fn name() -> &'static HeaderName {
-        &http::header::AGE
+impl From<u64> for Age {
+    fn from(seconds: u64) -> Self {
+        Age::new(seconds)
     }
+}
 
-    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
-    where
-        I: Iterator<Item = &'i HeaderValue>,
-    {
-        while let Some(h) = values.next() {
-            if let Some(ret) =
-                process_header(h.as_bytes()).map_err(|_| headers::Error::invalid())?
-            {
-                return Ok(ret);
+impl From<DeltaSeconds> for Age {
+    fn from(delta: DeltaSeconds) -> Self {
+        Age(delta)
+    }
+}
+
+impl From<Duration> for Age {
+    fn from(duration: Duration) -> Self {
+        Age(duration.into())
+    }
+}
+
+impl From<Age> for Duration {
+    fn from(age: Age) -> Self {
+        age.as_duration()
+    }
+}
+
+/// Parses a list member, which must be a delta-seconds, or with
+/// [`ParseMode::Compat`] start with one like browsers require.
+fn process_element(ctx: &mut Context, e: &Element) -> Result<Age, ParseError> {
+    let v = e.value();
+    let digits = leading_digits(v);
+    if ctx.mode != ParseMode::Compat {
+        let invalid = |kind, pos| Err(ParseError::new(kind, e.offset() + pos));
+        if digits.is_empty() {
+            return invalid(ParseErrorKind::InvalidToken, 0);
+        }
+        if let Some(&c) = v.get(digits.len()) {
+            if !is_wsp(c) {
+                return invalid(ParseErrorKind::InvalidToken, digits.len());
             }
+            let pos = v[digits.len()..].iter().take_while(|&&c| is_wsp(c)).count();
+            return invalid(ParseErrorKind::UnexpectedToken, digits.len() + pos);
         }
-        Err(headers::Error::invalid())
+    }
+    process_delta_seconds(ctx, digits, e.offset()).map(Age)
+}
+
+impl Age {
+    /// Like `headers::Header::decode`, but reports why the value was rejected.
+    pub fn try_decode<'i, I, V>(values: &mut I) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        Self::decode_with(values, ParseMode::default(), &mut ())
     }
 
-    fn encode<E>(&self, values: &mut E)
+    /// Decodes following the given [`ParseMode`], reporting problems that do
+    /// not prevent decoding to `diagnostics`.
+    pub fn decode_with<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
     where
-        E: Extend<HeaderValue>,
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
     {
-        let value = HeaderValue::from_str(&self.0.to_string()).unwrap();
-        values.extend(std::iter::once(value));
+        decode_lines(&mut values.map(|v| v.as_ref()), mode, diagnostics)
+    }
+
+    /// Like [`Age::try_decode`], for field line values given as byte
+    /// slices, e.g. read from a log.
+    pub fn parse_field_lines(lines: &[&[u8]]) -> Result<Self, ParseError> {
+        decode_lines(&mut lines.iter().copied(), ParseMode::default(), &mut ())
     }
 }
 
+fn decode_lines<'i, I>(
+    values: &mut I,
+    mode: ParseMode,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> Result<Age, ParseError>
+where
+    I: Iterator<Item = &'i [u8]>,
+{
+    let mut ctx = Context::new("age", mode, diagnostics);
+    if mode == ParseMode::Strict {
+        let h = values
+            .next()
+            .ok_or(ParseError::new(ParseErrorKind::Empty, 0).at("age", 0))?;
+        if values.next().is_some() {
+            return Err(ParseError::new(ParseErrorKind::MultipleValues, 0).at("age", 1));
+        }
+        if let Some(comma) = h.iter().position(|&c| c == b',') {
+            return Err(ParseError::new(ParseErrorKind::MultipleValues, comma).at("age", 0));
+        }
+        let e = ListIter::from_bytes(h)
+            .next()
+            .ok_or(ParseError::new(ParseErrorKind::Empty, 0).at("age", 0))?;
+        return process_element(&mut ctx, &e).map_err(|e| e.at("age", 0));
+    }
+    let mut list = ListIter::new(values);
+    let e = match list.next() {
+        Some(e) => e,
+        None => return Err(ParseError::new(ParseErrorKind::Empty, 0).at("age", list.lines())),
+    };
+    ctx.line = e.line();
+    let ret = process_element(&mut ctx, &e).map_err(|err| err.at("age", e.line()))?;
+    if let Some(e) = list.next() {
+        ctx.line = e.line();
+        ctx.warn(DiagnosticKind::DiscardedValues, e.offset());
+    }
+    Ok(ret)
+}
+
+impl Age {
+    /// The encoded field lines.
+    pub fn to_field_lines(&self) -> Vec<Bytes> {
+        vec![ser::single(self.0.to_string())]
+    }
+}
+
+impl_field_value!(Age);
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use headers::{HeaderMap, HeaderMapExt};
+    use crate::diagnostics::Diagnostics;
 
     #[test]
     fn test_decode() {
-        let decode = |vals| {
-            let mut h = HeaderMap::new();
-            for &val in vals {
-                h.append("Age", HeaderValue::from_static(val));
-            }
-            h.typed_get::<Age>()
-        };
+        let decode = |mut vals: std::slice::Iter<&str>| Age::try_decode(&mut vals).ok();
 
-        assert_eq!(decode(["0"].iter()), Some(Age(0)));
-        assert_eq!(decode(["123"].iter()), Some(Age(123)));
-        assert_eq!(decode(["000000000000000123"].iter()), Some(Age(123)));
+        assert_eq!(decode(["0"].iter()), Some(Age::new(0)));
+        assert_eq!(decode(["123"].iter()), Some(Age::new(123)));
+        assert_eq!(decode(["000000000000000123"].iter()), Some(Age::new(123)));
+        assert_eq!(decode(["2147483647"].iter()), Some(Age::new(2147483647)));
+        assert_eq!(decode(["2147483648"].iter()), Some(Age::OVERFLOW));
+        assert_eq!(decode(["123123123123123123"].iter()), Some(Age::OVERFLOW));
         assert_eq!(
-            decode(["123123123123123123"].iter()),
-            Some(Age(123123123123123123))
+            decode(["000000000000000000123"].iter()),
+            Some(Age::new(123))
         );
-        assert_eq!(decode(["000000000000000000123"].iter()), Some(Age(123)));
         assert_eq!(
             decode(["123123123123123123123"].iter()),
-            Some(Age(u64::MAX))
+            Some(Age::OVERFLOW)
+        );
+        assert_eq!(
+            decode([", ,,,   ,  123, ,    ,"].iter()),
+            Some(Age::new(123))
         );
-        assert_eq!(decode([", ,,,   ,  123, ,    ,"].iter()), Some(Age(123)));
-        assert_eq!(decode(["123, 234"].iter()), Some(Age(123)));
-        assert_eq!(decode(["123", ""].iter()), Some(Age(123)));
-        assert_eq!(decode(["123", ",, ,    ,"].iter()), Some(Age(123)));
+        assert_eq!(decode(["123, 234"].iter()), Some(Age::new(123)));
+        assert_eq!(decode(["123", ""].iter()), Some(Age::new(123)));
+        assert_eq!(decode(["123", ",, ,    ,"].iter()), Some(Age::new(123)));
         assert_eq!(
             decode([", ,,,   ,  123, ,    ,", ",, ,,   ,"].iter()),
-            Some(Age(123))
+            Some(Age::new(123))
+        );
+        assert_eq!(decode(["123, a"].iter()), Some(Age::new(123)));
+        assert_eq!(
+            decode([", ,,,   ,  123, ,a   ,"].iter()),
+            Some(Age::new(123))
         );
-        assert_eq!(decode(["123, a"].iter()), Some(Age(123)));
-        assert_eq!(decode([", ,,,   ,  123, ,a   ,"].iter()), Some(Age(123)));
         assert_eq!(
             decode([", ,,,   ,  123, ,   ,", "a"].iter()),
-            Some(Age(123))
+            Some(Age::new(123))
         );
         assert_eq!(
             decode([", ,,,   ,  123, ,   ,", ",, a, ,,   ,"].iter()),
-            Some(Age(123))
+            Some(Age::new(123))
         );
         assert_eq!(decode(["-0"].iter()), None);
         assert_eq!(decode(["-123"].iter()), None);
@@ -168,15 +365,163 @@ mod tests {
     }
 
     #[test]
-    fn test_encode() {
-        let encode = |val| {
-            let mut h = HeaderMap::new();
-            h.typed_insert(val);
-            h["age"].clone()
+    fn test_try_decode() {
+        let try_decode = |vals: &[&'static str]| {
+            Age::try_decode(&mut vals.iter()).map_err(|e| (e.kind(), e.line(), e.offset()))
+        };
+
+        assert_eq!(try_decode(&["123"]), Ok(Age::new(123)));
+        assert_eq!(try_decode(&[]), Err((ParseErrorKind::Empty, 0, 0)));
+        assert_eq!(try_decode(&["", " ,"]), Err((ParseErrorKind::Empty, 2, 0)));
+        assert_eq!(
+            try_decode(&["", " a"]),
+            Err((ParseErrorKind::InvalidToken, 1, 1))
+        );
+        assert_eq!(
+            try_decode(&["123 234"]),
+            Err((ParseErrorKind::UnexpectedToken, 0, 4))
+        );
+    }
+
+    #[test]
+    fn test_decode_with() {
+        let decode = |mode, vals: &[&'static str]| {
+            Age::decode_with(&mut vals.iter(), mode, &mut ()).map_err(|e| e.kind())
+        };
+
+        assert_eq!(decode(ParseMode::Strict, &["123"]), Ok(Age::new(123)));
+        assert_eq!(
+            decode(ParseMode::Strict, &["123, a"]),
+            Err(ParseErrorKind::MultipleValues)
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, &["123, 234"]),
+            Err(ParseErrorKind::MultipleValues)
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, &["123", "234"]),
+            Err(ParseErrorKind::MultipleValues)
+        );
+        assert_eq!(decode(ParseMode::Strict, &[""]), Err(ParseErrorKind::Empty));
+        assert_eq!(decode(ParseMode::Lenient, &["123, a"]), Ok(Age::new(123)));
+        assert_eq!(
+            decode(ParseMode::Lenient, &["123a"]),
+            Err(ParseErrorKind::InvalidToken)
+        );
+        assert_eq!(decode(ParseMode::Compat, &["123a"]), Ok(Age::new(123)));
+        assert_eq!(
+            decode(ParseMode::Compat, &[", , 123 x, a"]),
+            Ok(Age::new(123))
+        );
+        assert_eq!(decode(ParseMode::Compat, &[" ,", "5"]), Ok(Age::new(5)));
+        assert_eq!(
+            decode(ParseMode::Compat, &["-5"]),
+            Err(ParseErrorKind::InvalidInteger)
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let decode = |mode, vals: &[&'static str]| {
+            let mut diagnostics = Diagnostics::new();
+            Age::decode_with(&mut vals.iter(), mode, &mut diagnostics).unwrap();
+            diagnostics
+                .iter()
+                .map(|d| (d.kind(), d.line(), d.offset()))
+                .collect::<Vec<_>>()
         };
 
-        assert_eq!(encode(Age(0)), "0");
-        assert_eq!(encode(Age(123)), "123");
-        assert_eq!(encode(Age(123123123123123123)), "123123123123123123");
+        assert_eq!(decode(ParseMode::Lenient, &["123, ,"]), []);
+        assert_eq!(
+            decode(ParseMode::Lenient, &[" , 0123"]),
+            [(DiagnosticKind::LeadingZeros, 0, 3)]
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, &["123, a"]),
+            [(DiagnosticKind::DiscardedValues, 0, 5)]
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, &["", "123", " ,", "4"]),
+            [(DiagnosticKind::DiscardedValues, 3, 0)]
+        );
+        assert_eq!(
+            decode(ParseMode::Compat, &["12x, 3"]),
+            [(DiagnosticKind::DiscardedValues, 0, 5)]
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("123".parse::<Age>(), Ok(Age::new(123)));
+        assert_eq!(Age::try_from(&b" 5, 6"[..]), Ok(Age::new(5)));
+        assert_eq!(Age::parse_field_lines(&[b"", b"7"]), Ok(Age::new(7)));
+        assert_eq!(
+            "x".parse::<Age>().map_err(|e| (e.header(), e.kind())),
+            Err(("age", ParseErrorKind::InvalidToken))
+        );
+        assert_eq!(Age::new(60).to_string(), "60");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_current_age() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        // apparent_age = 5, corrected_age_value = 10 + 2, resident_time = 100
+        assert_eq!(
+            current_age(Some(Age::new(10)), at(995), at(998), at(1000), at(1100)),
+            Age::new(112)
+        );
+        // apparent_age = 50 wins over corrected_age_value = 0 + 2
+        assert_eq!(
+            current_age(None, at(950), at(998), at(1000), at(1000)),
+            Age::new(50)
+        );
+        // Date in the future and now before response_time count as zero.
+        assert_eq!(
+            current_age(None, at(2000), at(1000), at(1000), at(900)),
+            Age::new(0)
+        );
+        assert_eq!(
+            current_age(Some(Age::OVERFLOW), at(0), at(0), at(1), at(1)),
+            Age::OVERFLOW
+        );
+        assert_eq!(
+            current_age(Some(Age::new(2147483600)), at(0), at(0), at(0), at(100)),
+            Age::OVERFLOW
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_for_stored_response() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let h = [("age", "10"), ("date", "Sun, 06 Nov 1994 08:49:37 GMT")];
+
+        let received = at(784111777);
+        let age =
+            Age::for_stored_response(&h, received, received, received + Duration::from_secs(60));
+        assert_eq!(age, Age::new(70));
+        let h = [("age", "10"), ("date", "garbage")];
+        let age = Age::for_stored_response(&h, received, received, received);
+        assert_eq!(age, Age::new(10));
+        assert_eq!(age.to_string(), "10");
+        assert_eq!(Age::OVERFLOW.to_string(), "2147483648");
+    }
+
+    #[test]
+    fn test_encode() {
+        let encode = |val: Age| val.to_field_lines()[0].clone();
+
+        assert_eq!(encode(Age::new(0)), "0");
+        assert_eq!(encode(Age::new(123)), "123");
+        assert_eq!(encode(Age::new(123123123123123123)), "2147483648");
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Age::new(90).seconds(), 90);
+        assert_eq!(Age::from(Duration::from_millis(90_500)), Age::new(90));
+        assert_eq!(Duration::from(Age::new(90)), Duration::from_secs(90));
     }
 }
@@ -0,0 +1,494 @@
+//! `Arbitrary` impls, enabled with the `arbitrary` feature
+//!
+//! Only values that decoding can produce are generated, so that they survive
+//! an encoding round trip: field names are lowercase tokens and directive
+//! arguments are tokens or quoted-strings. `Cache-Control` can have
+//! conflicting directives such as `public` with `private`, which decoding
+//! keeps too.
+//!
+//! `RequestCacheControl` and `ResponseCacheControl` only have the directives
+//! of their direction. `RawCacheControl` is decoded from field lines with any
+//! casing, spacing and empty list elements, and sometimes modified.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+use bytes::BytesMut;
+
+use crate::age::Age;
+use crate::cache_control::{
+    CacheControl, Direction, RawCacheControl, RequestCacheControl, ResponseCacheControl, DIRECTIVES,
+};
+use crate::date::HttpDate;
+use crate::delta_seconds::DeltaSeconds;
+use crate::expires::Expires;
+use crate::ser::put_quoted;
+
+const TCHAR: &[u8] =
+    b"!#$%&'*+-.^_`|~0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn token(u: &mut Unstructured, lowercase: bool) -> Result<String> {
+    let chars = if lowercase {
+        &TCHAR[..TCHAR.len() - 26]
+    } else {
+        TCHAR
+    };
+    let len = u.int_in_range(1..=16)?;
+    (0..len)
+        .map(|_| u.choose(chars).map(|&c| char::from(c)))
+        .collect()
+}
+
+/// Printable ASCII without leading or trailing whitespace.
+fn field_value(u: &mut Unstructured) -> Result<Vec<u8>> {
+    let len = u.int_in_range(0..=40)?;
+    let v = (0..len)
+        .map(|_| u.int_in_range(0x20..=0x7e))
+        .collect::<Result<Vec<u8>>>()?;
+    Ok(v.trim_ascii().to_vec())
+}
+
+fn field_names(u: &mut Unstructured) -> Result<Option<Vec<String>>> {
+    if !u.arbitrary()? {
+        return Ok(None);
+    }
+    let len = u.int_in_range(0..=3)?;
+    (0..len)
+        .map(|_| token(u, true))
+        .collect::<Result<_>>()
+        .map(Some)
+}
+
+fn extension(u: &mut Unstructured) -> Result<(String, Option<Vec<u8>>)> {
+    let mut name = token(u, false)?;
+    if DIRECTIVES.iter().any(|d| d.eq_ignore_ascii_case(&name)) {
+        name.push('x');
+    }
+    let argument = match u.int_in_range(0..=2)? {
+        0 => None,
+        1 => Some(token(u, false)?.into_bytes()),
+        _ => {
+            let mut quoted = BytesMut::new();
+            put_quoted(&mut quoted, &field_value(u)?);
+            Some(quoted.to_vec())
+        }
+    };
+    Ok((name, argument))
+}
+
+impl<'a> Arbitrary<'a> for DeltaSeconds {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let seconds = u.int_in_range(0..=DeltaSeconds::OVERFLOW.seconds())?;
+        Ok(DeltaSeconds::new(seconds))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Age {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        DeltaSeconds::arbitrary(u).map(Age::from)
+    }
+}
+
+impl<'a> Arbitrary<'a> for HttpDate {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let secs = u.int_in_range(HttpDate::MIN.unix_secs()..=HttpDate::MAX.unix_secs())?;
+        Ok(HttpDate::from_unix_secs_saturating(secs))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Expires {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.arbitrary()? {
+            return HttpDate::arbitrary(u).map(Expires::Valid);
+        }
+        // Decoding a single line only fails in strict mode.
+        Expires::parse_field_lines(&[&field_value(u)?]).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+/// A `CacheControl` with only the directives defined for `direction`.
+fn cache_control(u: &mut Unstructured, direction: Direction) -> Result<CacheControl> {
+    let allows = |name| direction.allows(name);
+    let mut b = CacheControl::builder();
+    if let Some(v) = u.arbitrary::<Option<DeltaSeconds>>()? {
+        b = b.max_age(v);
+    }
+    if allows("max-stale") {
+        match u.int_in_range(0..=2)? {
+            0 => {}
+            1 => b = b.max_stale_any(),
+            _ => b = b.max_stale(DeltaSeconds::arbitrary(u)?),
+        }
+    }
+    if allows("min-fresh") {
+        if let Some(v) = u.arbitrary::<Option<DeltaSeconds>>()? {
+            b = b.min_fresh(v);
+        }
+    }
+    // Only the response form of `no-cache` takes a list of fields.
+    match field_names(u)? {
+        Some(fields) if direction != Direction::Request => b = b.no_cache_fields(fields),
+        Some(_) => b = b.no_cache(),
+        None => {}
+    }
+    if u.arbitrary()? {
+        b = b.no_store();
+    }
+    if u.arbitrary()? {
+        b = b.no_transform();
+    }
+    if allows("only-if-cached") && u.arbitrary()? {
+        b = b.only_if_cached();
+    }
+    if allows("must-revalidate") && u.arbitrary()? {
+        b = b.must_revalidate();
+    }
+    if allows("must-understand") && u.arbitrary()? {
+        b = b.must_understand();
+    }
+    if u.arbitrary()? {
+        b = b.transform();
+    }
+    if allows("private") {
+        if let Some(fields) = field_names(u)? {
+            b = b.private_fields(fields);
+        }
+    }
+    if allows("proxy-revalidate") && u.arbitrary()? {
+        b = b.proxy_revalidate();
+    }
+    if allows("public") && u.arbitrary()? {
+        b = b.public();
+    }
+    if allows("s-maxage") {
+        if let Some(v) = u.arbitrary::<Option<DeltaSeconds>>()? {
+            b = b.s_maxage(v);
+        }
+    }
+    if allows("immutable") && u.arbitrary()? {
+        b = b.immutable();
+    }
+    if allows("stale-while-revalidate") {
+        if let Some(v) = u.arbitrary::<Option<DeltaSeconds>>()? {
+            b = b.stale_while_revalidate(v);
+        }
+    }
+    if let Some(v) = u.arbitrary::<Option<DeltaSeconds>>()? {
+        b = b.stale_if_error(v);
+    }
+    for _ in 0..u.int_in_range(0..=3)? {
+        let (name, argument) = extension(u)?;
+        b = b.other(name, argument);
+    }
+    b.build_with_conflicts().map_err(|_| Error::IncorrectFormat)
+}
+
+impl<'a> Arbitrary<'a> for CacheControl {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        cache_control(u, Direction::Any)
+    }
+}
+
+impl<'a> Arbitrary<'a> for RequestCacheControl {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let cc = cache_control(u, Direction::Request)?;
+        RequestCacheControl::try_from(cc).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for ResponseCacheControl {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let cc = cache_control(u, Direction::Response)?;
+        ResponseCacheControl::try_from(cc).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+/// Randomizes the case of ASCII letters.
+fn mixed_case(u: &mut Unstructured, s: &str) -> Result<String> {
+    s.chars()
+        .map(|c| {
+            Ok(if u.arbitrary()? {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            })
+        })
+        .collect()
+}
+
+/// A directive as a sender might write it, with any casing and quoting.
+fn raw_directive(u: &mut Unstructured) -> Result<Vec<u8>> {
+    let (name, argument) = if u.arbitrary()? {
+        let name = u.choose(&DIRECTIVES)?;
+        let argument = match u.int_in_range(0..=2)? {
+            0 => None,
+            1 => Some(DeltaSeconds::arbitrary(u)?.to_string().into_bytes()),
+            _ => Some(format!("\"{}\"", token(u, true)?).into_bytes()),
+        };
+        (mixed_case(u, name)?, argument)
+    } else {
+        extension(u)?
+    };
+    let mut ret = name.into_bytes();
+    if let Some(argument) = argument {
+        ret.push(b'=');
+        ret.extend(argument);
+    }
+    Ok(ret)
+}
+
+impl<'a> Arbitrary<'a> for RawCacheControl {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        const SEPARATORS: [&[u8]; 5] = [b",", b", ", b" ,", b" , ,\t", b",,"];
+        let mut lines = Vec::new();
+        for _ in 0..u.int_in_range(1..=3)? {
+            let mut line = Vec::new();
+            for i in 0..u.int_in_range(1..=4)? {
+                if i > 0 {
+                    line.extend(*u.choose(&SEPARATORS)?);
+                }
+                line.extend(raw_directive(u)?);
+            }
+            lines.push(line);
+        }
+        let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+        let mut raw =
+            RawCacheControl::parse_field_lines(&lines).map_err(|_| Error::IncorrectFormat)?;
+        // Modified directives are encoded in canonical form instead.
+        if u.arbitrary()? {
+            let directives = raw.directives_mut();
+            directives.reverse();
+            if u.arbitrary()? {
+                directives.pop();
+            }
+        }
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use core::fmt::Debug;
+    use proptest::collection::vec;
+    use proptest::prelude::{
+        any, prop_assert, prop_assert_eq, prop_oneof, proptest, Strategy, TestCaseError,
+    };
+    use proptest::sample::select;
+
+    use crate::error::ParseError;
+
+    /// Drives an `Arbitrary` impl with bytes from proptest, which shrinks them.
+    fn arbitrary<T>() -> impl Strategy<Value = T>
+    where
+        T: for<'a> Arbitrary<'a> + Debug,
+    {
+        vec(any::<u8>(), 0..512).prop_filter_map("not enough data", |data| {
+            T::arbitrary(&mut Unstructured::new(&data)).ok()
+        })
+    }
+
+    const PIECES: [&str; 16] = [
+        "MAX-AGE",
+        "x",
+        "\"",
+        "\\",
+        "=",
+        ",",
+        " ",
+        "\t",
+        "0",
+        "7",
+        "2147483648",
+        "99999999999999999999",
+        "\"a, b\"",
+        "Sun, 06 Nov 1994 08:49:37 GMT",
+        "Sunday, 06-Nov-94 08:49:37 GMT",
+        "Sun Nov  6 08:49:37 1994",
+    ];
+
+    /// Field lines made of pieces of Cache-Control, Age and Expires values,
+    /// mixed with arbitrary bytes.
+    fn field_lines() -> impl Strategy<Value = Vec<Vec<u8>>> {
+        let piece = prop_oneof![
+            select(&DIRECTIVES[..]).prop_map(str::as_bytes),
+            select(&PIECES[..]).prop_map(str::as_bytes),
+        ];
+        let line = prop_oneof![
+            vec(piece, 0..8).prop_map(|pieces| pieces.concat()),
+            vec(any::<u8>(), 0..32),
+        ];
+        vec(line, 0..3)
+    }
+
+    fn roundtrip<T: Debug + PartialEq>(
+        value: &T,
+        encode: fn(&T) -> Vec<Bytes>,
+        decode: impl Fn(&[&[u8]]) -> Result<T, ParseError>,
+    ) -> core::result::Result<(), TestCaseError> {
+        let lines = encode(value);
+        let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+        let decoded = decode(&lines);
+        prop_assert_eq!(decoded.as_ref(), Ok(value));
+        Ok(())
+    }
+
+    /// Encoding what was decoded gives a value which encodes the same.
+    fn idempotent<T: Debug + PartialEq>(
+        lines: &[Vec<u8>],
+        encode: fn(&T) -> Vec<Bytes>,
+        decode: impl Fn(&[&[u8]]) -> Result<T, ParseError>,
+    ) -> core::result::Result<(), TestCaseError> {
+        let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+        if let Ok(value) = decode(&lines) {
+            let encoded = encode(&value);
+            let lines: Vec<&[u8]> = encoded.iter().map(|l| &l[..]).collect();
+            prop_assert_eq!(decode(&lines).map(|v| encode(&v)), Ok(encoded.clone()));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip<T>(value: &T) -> core::result::Result<(), TestCaseError>
+    where
+        T: Debug + PartialEq + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let json = serde_json::to_value(value).unwrap();
+        let deserialized = serde_json::from_value::<T>(json).ok();
+        prop_assert_eq!(deserialized.as_ref(), Some(value));
+        Ok(())
+    }
+
+    /// Decodes with `decode`, except that no field lines are no directives,
+    /// which is how an empty Cache-Control is encoded.
+    fn or_default<T: Default>(
+        decode: fn(&[&[u8]]) -> Result<T, ParseError>,
+    ) -> impl Fn(&[&[u8]]) -> Result<T, ParseError> {
+        move |lines| match lines {
+            [] => Ok(T::default()),
+            _ => decode(lines),
+        }
+    }
+
+    /// An unmodified `RawCacheControl` must come back byte-identical, while
+    /// a modified one, encoded in canonical form, keeps its directives.
+    fn same_raw(
+        decoded: &RawCacheControl,
+        raw: &RawCacheControl,
+    ) -> core::result::Result<(), TestCaseError> {
+        if raw.is_modified() {
+            prop_assert_eq!(decoded.directives(), raw.directives());
+        } else {
+            prop_assert_eq!(decoded, raw);
+        }
+        Ok(())
+    }
+
+    fn roundtrip_raw(raw: &RawCacheControl) -> core::result::Result<(), TestCaseError> {
+        let lines = raw.to_field_lines();
+        let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+        let decoded = or_default(RawCacheControl::parse_field_lines)(&lines);
+        prop_assert!(decoded.is_ok(), "{:?}", decoded);
+        same_raw(&decoded.unwrap(), raw)
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(
+            cc in arbitrary::<CacheControl>(),
+            age in arbitrary::<Age>(),
+            expires in arbitrary::<Expires>(),
+        ) {
+            roundtrip(
+                &cc,
+                CacheControl::to_field_lines,
+                or_default(CacheControl::parse_field_lines),
+            )?;
+            roundtrip(&age, Age::to_field_lines, Age::parse_field_lines)?;
+            roundtrip(&expires, Expires::to_field_lines, Expires::parse_field_lines)?;
+        }
+
+        #[test]
+        fn test_roundtrip_cache_control_forms(
+            request in arbitrary::<RequestCacheControl>(),
+            response in arbitrary::<ResponseCacheControl>(),
+            raw in arbitrary::<RawCacheControl>(),
+        ) {
+            roundtrip(
+                &request,
+                RequestCacheControl::to_field_lines,
+                or_default(RequestCacheControl::parse_field_lines),
+            )?;
+            roundtrip(
+                &response,
+                ResponseCacheControl::to_field_lines,
+                or_default(ResponseCacheControl::parse_field_lines),
+            )?;
+            roundtrip_raw(&raw)?;
+        }
+
+        #[test]
+        fn test_roundtrip_values(delta in arbitrary::<DeltaSeconds>(), date in arbitrary::<HttpDate>()) {
+            prop_assert_eq!(DeltaSeconds::parse(delta.to_string().as_bytes()), Ok(delta));
+            prop_assert_eq!(HttpDate::parse(&date.to_imf_fixdate()), Ok(date));
+        }
+
+        #[test]
+        fn test_idempotent(lines in field_lines()) {
+            idempotent(
+                &lines,
+                CacheControl::to_field_lines,
+                or_default(CacheControl::parse_field_lines),
+            )?;
+            idempotent(
+                &lines,
+                RequestCacheControl::to_field_lines,
+                or_default(RequestCacheControl::parse_field_lines),
+            )?;
+            idempotent(
+                &lines,
+                ResponseCacheControl::to_field_lines,
+                or_default(ResponseCacheControl::parse_field_lines),
+            )?;
+            idempotent(
+                &lines,
+                RawCacheControl::to_field_lines,
+                or_default(RawCacheControl::parse_field_lines),
+            )?;
+            idempotent(&lines, Age::to_field_lines, Age::parse_field_lines)?;
+            idempotent(&lines, Expires::to_field_lines, Expires::parse_field_lines)?;
+            // Decoded field lines are forwarded byte for byte.
+            let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+            if let Ok(raw) = RawCacheControl::parse_field_lines(&lines) {
+                prop_assert_eq!(raw.to_field_lines(), lines);
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn test_serde_roundtrip(
+            cc in arbitrary::<CacheControl>(),
+            request in arbitrary::<RequestCacheControl>(),
+            response in arbitrary::<ResponseCacheControl>(),
+            raw in arbitrary::<RawCacheControl>(),
+            age in arbitrary::<Age>(),
+            expires in arbitrary::<Expires>(),
+            lines in field_lines(),
+        ) {
+            serde_roundtrip(&cc)?;
+            serde_roundtrip(&request)?;
+            serde_roundtrip(&response)?;
+            let json = serde_json::to_value(&raw).unwrap();
+            same_raw(&serde_json::from_value(json).unwrap(), &raw)?;
+            serde_roundtrip(&age)?;
+            serde_roundtrip(&expires)?;
+            let lines: Vec<&[u8]> = lines.iter().map(|l| &l[..]).collect();
+            if let Ok(decoded) = or_default(CacheControl::parse_field_lines)(&lines) {
+                serde_roundtrip(&decoded)?;
+            }
+        }
+    }
+}
@@ -1,359 +1,2278 @@
-use bytes::{BufMut, BytesMut};
-use headers::{Header, HeaderName, HeaderValue};
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bytes::Bytes;
 use logos::Logos;
 
-use crate::util::parse_u64;
+use crate::abnf::{is_tchar, is_wsp, unquote};
+use crate::delta_seconds::{process_delta_seconds, DeltaSeconds};
+use crate::diagnostics::{Context, DiagnosticKind, DiagnosticSink};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::list::{Element, ListIter};
+use crate::mode::ParseMode;
+use crate::ser::{can_quote, is_quotable, is_token, ListWriter};
+use crate::util::leading_digits;
 
-#[derive(Clone, Default, Debug)]
+/// The directives defined by RFC9111, RFC8246 and RFC5861, in encoding order.
+/// Extension directives cannot be named after them.
+pub const DIRECTIVES: [&str; 17] = [
+    "max-age",
+    "max-stale",
+    "min-fresh",
+    "no-cache",
+    "no-store",
+    "no-transform",
+    "only-if-cached",
+    "must-revalidate",
+    "must-understand",
+    "transform",
+    "private",
+    "proxy-revalidate",
+    "public",
+    "s-maxage",
+    "immutable",
+    "stale-while-revalidate",
+    "stale-if-error",
+];
+
+#[derive(Clone, Default, Debug, PartialEq)]
 pub struct CacheControl {
-    max_age: Option<u64>,
-    max_stale: Option<u64>,
-    min_fresh: Option<u64>,
-    no_cache: Option<Vec<HeaderName>>,
+    max_age: Option<DeltaSeconds>,
+    max_stale: Option<Option<DeltaSeconds>>,
+    min_fresh: Option<DeltaSeconds>,
+    no_cache: Option<Vec<String>>,
     no_store: bool,
     no_transform: bool,
     only_if_cached: bool,
     must_revalidate: bool,
     must_understand: bool,
     transform: bool,
-    private: Option<Vec<HeaderName>>,
+    private: Option<Vec<String>>,
     proxy_revalidate: bool,
     public: bool,
-    s_maxage: Option<u64>,
+    s_maxage: Option<DeltaSeconds>,
     immutable: bool,
-    stale_while_revalidate: Option<u64>,
-    stale_if_error: Option<u64>,
+    stale_while_revalidate: Option<DeltaSeconds>,
+    stale_if_error: Option<DeltaSeconds>,
     other: Vec<(String, Option<Vec<u8>>)>,
 }
 
-#[derive(Logos, Debug, PartialEq)]
-#[logos(skip r"[ \t]+")]
-enum CCToken {
-    #[token(b"max-age")]
-    MaxAge,
-    #[token(b"max-stale")]
-    MaxStale,
-    #[token(b"min-fresh")]
-    MinFresh,
-    #[token(b"no-cache")]
-    NoCache,
-    #[token(b"no-store")]
-    NoStore,
-    #[token(b"no-transform")]
-    NoTransform,
-    #[token(b"only-if-cached")]
-    OnlyIfCached,
-    #[token(b"must-revalidate")]
-    MustRevalidate,
-    #[token(b"must-understand")]
-    MustUnderstand,
-    #[token(b"transform")]
-    Transform,
-    #[token(b"private")]
-    Private,
-    #[token(b"proxy-revalidate")]
-    ProxyRevalidate,
-    #[token(b"public")]
-    Public,
-    #[token(b"s-maxage")]
-    SMaxage,
-    #[token(b"immutable")]
-    Immutable,
-    #[token(b"stale-while-revalidate")]
-    StaleWhileRevalidate,
-    #[token(b"stale-if-error")]
-    StaleIfError,
-    #[regex(b"[!#$%&'*+.^_`|~0-9a-zA-Z-]+", |l| Some(std::str::from_utf8(l.slice()).unwrap().to_owned()))]
-    Other(String),
-    #[token(b",")]
-    Comma,
-    #[token(b"=")]
-    Equals,
+impl CacheControl {
+    pub fn builder() -> CacheControlBuilder {
+        CacheControlBuilder::default()
+    }
+
+    pub fn max_age(&self) -> Option<DeltaSeconds> {
+        self.max_age
+    }
+
+    /// `Some(None)` if `max-stale` has no argument, meaning that a stale
+    /// response of any age is acceptable.
+    pub fn max_stale(&self) -> Option<Option<DeltaSeconds>> {
+        self.max_stale
+    }
+
+    pub fn min_fresh(&self) -> Option<DeltaSeconds> {
+        self.min_fresh
+    }
+
+    /// `Some` if `no-cache` is present; an empty slice means it applies to the
+    /// whole response rather than to the listed fields, which are lowercase.
+    pub fn no_cache(&self) -> Option<&[String]> {
+        self.no_cache.as_deref()
+    }
+
+    pub fn no_store(&self) -> bool {
+        self.no_store
+    }
+
+    pub fn no_transform(&self) -> bool {
+        self.no_transform
+    }
+
+    pub fn only_if_cached(&self) -> bool {
+        self.only_if_cached
+    }
+
+    pub fn must_revalidate(&self) -> bool {
+        self.must_revalidate
+    }
+
+    pub fn must_understand(&self) -> bool {
+        self.must_understand
+    }
+
+    pub fn transform(&self) -> bool {
+        self.transform
+    }
+
+    /// `Some` if `private` is present; an empty slice means it applies to the
+    /// whole response rather than to the listed fields, which are lowercase.
+    pub fn private(&self) -> Option<&[String]> {
+        self.private.as_deref()
+    }
+
+    pub fn proxy_revalidate(&self) -> bool {
+        self.proxy_revalidate
+    }
+
+    pub fn public(&self) -> bool {
+        self.public
+    }
+
+    pub fn s_maxage(&self) -> Option<DeltaSeconds> {
+        self.s_maxage
+    }
+
+    pub fn immutable(&self) -> bool {
+        self.immutable
+    }
+
+    pub fn stale_while_revalidate(&self) -> Option<DeltaSeconds> {
+        self.stale_while_revalidate
+    }
+
+    pub fn stale_if_error(&self) -> Option<DeltaSeconds> {
+        self.stale_if_error
+    }
+
+    /// Unknown directives, with their argument exactly as received.
+    pub fn other(&self) -> &[(String, Option<Vec<u8>>)] {
+        &self.other
+    }
+
+    /// Which of [`DIRECTIVES`] are present, in the same order.
+    fn present(&self) -> [bool; DIRECTIVES.len()] {
+        [
+            self.max_age.is_some(),
+            self.max_stale.is_some(),
+            self.min_fresh.is_some(),
+            self.no_cache.is_some(),
+            self.no_store,
+            self.no_transform,
+            self.only_if_cached,
+            self.must_revalidate,
+            self.must_understand,
+            self.transform,
+            self.private.is_some(),
+            self.proxy_revalidate,
+            self.public,
+            self.s_maxage.is_some(),
+            self.immutable,
+            self.stale_while_revalidate.is_some(),
+            self.stale_if_error.is_some(),
+        ]
+    }
+
+    fn has(&self, directive: &str) -> bool {
+        DIRECTIVES
+            .iter()
+            .zip(self.present())
+            .any(|(&name, present)| present && name == directive)
+    }
 }
 
-#[derive(Logos, Debug, PartialEq)]
-#[logos(skip r"[ \t]+")]
-enum CCArg {
-    #[regex(b"[!#$%&'*+.^_`|~0-9a-zA-Z-]+")]
-    Token,
-    #[regex(br#""([^"\\]*)""#, priority = 2)]
-    Quoted,
-    #[regex(br#""([^"\\]|\\.)*""#, priority = 1)]
-    QuotedEscape,
+impl CacheControl {
+    /// Combines two policies so that the most restrictive one wins, e.g. an
+    /// origin's Cache-Control with a per-route override.
+    ///
+    /// - `max-age` and `s-maxage` take the smaller value, and `min-fresh` the
+    ///   larger one.
+    /// - `max-stale`, `stale-while-revalidate` and `stale-if-error` allow
+    ///   serving stale responses, so they are only kept if present in both
+    ///   policies, with the smaller value; a bare `max-stale` is the least
+    ///   restrictive.
+    /// - `no-store`, `must-revalidate` and the other flags that restrict
+    ///   caching are present if present in either policy, while `public`,
+    ///   `immutable`, `transform` and `must-understand`, which lets caches
+    ///   that understand the status code ignore `no-store`, must be present
+    ///   in both.
+    /// - The field names of `no-cache` and `private` are united, unless either
+    ///   policy applies the directive to the whole response.
+    /// - Unknown directives are kept, skipping those already present.
+    pub fn merge(&self, other: &CacheControl) -> CacheControl {
+        let mut ret = self.clone();
+        ret.restrict(other);
+        ret
+    }
+
+    /// Like [`CacheControl::merge`], but in place.
+    pub fn restrict(&mut self, other: &CacheControl) {
+        self.max_age = min(self.max_age, other.max_age);
+        self.max_stale = match (self.max_stale, other.max_stale) {
+            (Some(Some(a)), Some(Some(b))) => Some(Some(a.min(b))),
+            (Some(None), v) | (v, Some(None)) => v,
+            (None, _) | (_, None) => None,
+        };
+        self.min_fresh = self.min_fresh.max(other.min_fresh);
+        union(&mut self.no_cache, &other.no_cache);
+        self.no_store |= other.no_store;
+        self.no_transform |= other.no_transform;
+        self.only_if_cached |= other.only_if_cached;
+        self.must_revalidate |= other.must_revalidate;
+        self.must_understand &= other.must_understand;
+        self.transform &= other.transform;
+        union(&mut self.private, &other.private);
+        self.proxy_revalidate |= other.proxy_revalidate;
+        self.public &= other.public;
+        self.s_maxage = min(self.s_maxage, other.s_maxage);
+        self.immutable &= other.immutable;
+        self.stale_while_revalidate =
+            min_allowed(self.stale_while_revalidate, other.stale_while_revalidate);
+        self.stale_if_error = min_allowed(self.stale_if_error, other.stale_if_error);
+        for (name, argument) in &other.other {
+            if !self.other.iter().any(|(n, _)| n == name) {
+                self.other.push((name.clone(), argument.clone()));
+            }
+        }
+    }
 }
 
-#[derive(Logos, Debug, PartialEq)]
-#[logos(skip r"[ \t]+")]
-enum TokenList {
-    #[regex(b"[!#$%&'*+.^_`|~0-9a-zA-Z-]+")]
-    Token,
-    #[token(b",")]
-    Comma,
+/// The smaller of two limits, where `None` means no limit.
+fn min(a: Option<DeltaSeconds>, b: Option<DeltaSeconds>) -> Option<DeltaSeconds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
 }
 
-#[derive(Logos, Debug, PartialEq)]
-enum Integer {
-    #[regex(b"[0-9]+")]
-    Int,
+/// The smaller of two allowances, where `None` means none is allowed.
+fn min_allowed(a: Option<DeltaSeconds>, b: Option<DeltaSeconds>) -> Option<DeltaSeconds> {
+    Some(a?.min(b?))
 }
 
-fn header_list(i: &[u8]) -> Result<Vec<HeaderName>, ()> {
-    let mut ret = Vec::default();
-    let mut l = TokenList::lexer(i);
-    let mut comma = true;
-    while let Some(t) = l.next() {
-        match t? {
-            TokenList::Comma => comma = true,
-            TokenList::Token if comma => {
-                ret.push(HeaderName::from_bytes(l.slice()).unwrap());
-                comma = false;
+/// Unites field-name lists, where an empty list means the whole response.
+fn union(a: &mut Option<Vec<String>>, b: &Option<Vec<String>>) {
+    match (a.as_mut(), b) {
+        (_, None) => {}
+        (None, b) => *a = b.clone(),
+        (Some(a), Some(_)) if a.is_empty() => {}
+        (Some(a), Some(b)) if b.is_empty() => a.clear(),
+        (Some(a), Some(b)) => {
+            for name in b {
+                if !a.contains(name) {
+                    a.push(name.clone());
+                }
             }
-            TokenList::Token => return Err(()),
-        };
+        }
     }
-    Ok(ret)
 }
 
-fn process_directive(
-    cc: &mut CacheControl,
-    directive: CCToken,
-    argument: Option<&[u8]>,
-    raw_argument: Option<&[u8]>,
-) -> Result<(), ()> {
-    match (directive, argument) {
-        (CCToken::MaxAge, Some(v)) => cc.max_age = Some(parse_u64::<true>(v)?),
-        (CCToken::MaxStale, Some(v)) => cc.max_stale = Some(parse_u64::<true>(v)?),
-        (CCToken::MinFresh, Some(v)) => cc.min_fresh = Some(parse_u64::<true>(v)?),
-        (CCToken::NoCache, Some(v)) => cc.no_cache = Some(header_list(v)?),
-        (CCToken::NoCache, None) => cc.no_cache = None,
-        (CCToken::NoStore, None) => cc.no_store = true,
-        (CCToken::NoTransform, None) => cc.no_transform = true,
-        (CCToken::OnlyIfCached, None) => cc.only_if_cached = true,
-        (CCToken::MustRevalidate, None) => cc.must_revalidate = true,
-        (CCToken::MustUnderstand, None) => cc.must_understand = true,
-        (CCToken::Transform, None) => cc.transform = true,
-        (CCToken::Private, Some(v)) => cc.private = Some(header_list(v)?),
-        (CCToken::Private, None) => cc.private = None,
-        (CCToken::ProxyRevalidate, None) => cc.proxy_revalidate = true,
-        (CCToken::Public, None) => cc.public = true,
-        (CCToken::SMaxage, Some(v)) => cc.s_maxage = Some(parse_u64::<true>(v)?),
-        (CCToken::Immutable, None) => cc.immutable = true,
-        (CCToken::StaleWhileRevalidate, Some(v)) => {
-            cc.stale_while_revalidate = Some(parse_u64::<true>(v)?)
-        }
-        (CCToken::StaleIfError, Some(v)) => cc.stale_if_error = Some(parse_u64::<true>(v)?),
-        (CCToken::Other(s), Some(_)) => cc.other.push((s, Some(raw_argument.unwrap().to_vec()))),
-        (CCToken::Other(s), None) => cc.other.push((s, None)),
-        _ => return Err(()),
-    };
-    Ok(())
+/// Borrowed form of [`CacheControl`], pointing into the field values it was
+/// decoded from.
+///
+/// Decoding only allocates to unescape quoted-strings, and to hold unknown
+/// directives if there are any.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct CacheControlRef<'a> {
+    max_age: Option<DeltaSeconds>,
+    max_stale: Option<Option<DeltaSeconds>>,
+    min_fresh: Option<DeltaSeconds>,
+    no_cache: Option<FieldNames<'a>>,
+    no_store: bool,
+    no_transform: bool,
+    only_if_cached: bool,
+    must_revalidate: bool,
+    must_understand: bool,
+    transform: bool,
+    private: Option<FieldNames<'a>>,
+    proxy_revalidate: bool,
+    public: bool,
+    s_maxage: Option<DeltaSeconds>,
+    immutable: bool,
+    stale_while_revalidate: Option<DeltaSeconds>,
+    stale_if_error: Option<DeltaSeconds>,
+    other: Vec<(&'a str, Option<&'a [u8]>)>,
 }
 
-fn process_argument<'source>(
-    cc: &mut CacheControl,
-    directive: CCToken,
-    l: logos::Lexer<'source, CCToken>,
-) -> Result<logos::Lexer<'source, CCToken>, ()> {
-    let mut subl = l.morph::<CCArg>();
-    match subl.next().ok_or(())?? {
-        CCArg::Token => {
-            process_directive(cc, directive, Some(subl.slice()), Some(subl.slice())).ok();
-        }
-        CCArg::Quoted => {
-            let slice = subl.slice();
-            process_directive(
-                cc,
-                directive,
-                Some(&slice[1..slice.len() - 1]),
-                Some(subl.slice()),
-            )
-            .ok();
-        }
-        CCArg::QuotedEscape => {
-            let slice = subl.slice();
-            let mut vec = slice[1..slice.len() - 1].to_vec();
-            vec.retain(|&c| c != b'\\');
-            process_directive(cc, directive, Some(&vec[..]), Some(subl.slice())).ok();
-        }
+impl<'a> CacheControlRef<'a> {
+    pub fn max_age(&self) -> Option<DeltaSeconds> {
+        self.max_age
     }
-    Ok(subl.morph::<CCToken>())
-}
 
-fn process_header(cc: &mut CacheControl, i: &[u8]) -> Result<(), ()> {
-    let mut l = CCToken::lexer(i);
-    let mut cur = None;
-    while let Some(t) = l.next() {
-        match t? {
-            CCToken::Comma if cur.is_some() => {
-                process_directive(cc, std::mem::take(&mut cur).unwrap(), None, None).ok();
-            }
-            CCToken::Comma => {}
-            CCToken::Equals if cur.is_some() => {
-                l = process_argument(cc, std::mem::take(&mut cur).unwrap(), l)?;
-            }
-            CCToken::Equals => {
-                return Err(());
-            }
-            _ if cur.is_some() => {
-                return Err(());
-            }
-            directive => {
-                cur = Some(directive);
-            }
-        }
+    /// See [`CacheControl::max_stale`].
+    pub fn max_stale(&self) -> Option<Option<DeltaSeconds>> {
+        self.max_stale
     }
-    if cur.is_some() {
-        process_directive(cc, std::mem::take(&mut cur).unwrap(), None, None).ok();
+
+    pub fn min_fresh(&self) -> Option<DeltaSeconds> {
+        self.min_fresh
+    }
+
+    /// See [`CacheControl::no_cache`].
+    pub fn no_cache(&self) -> Option<&FieldNames<'a>> {
+        self.no_cache.as_ref()
+    }
+
+    pub fn no_store(&self) -> bool {
+        self.no_store
+    }
+
+    pub fn no_transform(&self) -> bool {
+        self.no_transform
+    }
+
+    pub fn only_if_cached(&self) -> bool {
+        self.only_if_cached
+    }
+
+    pub fn must_revalidate(&self) -> bool {
+        self.must_revalidate
+    }
+
+    pub fn must_understand(&self) -> bool {
+        self.must_understand
+    }
+
+    pub fn transform(&self) -> bool {
+        self.transform
+    }
+
+    /// See [`CacheControl::private`].
+    pub fn private(&self) -> Option<&FieldNames<'a>> {
+        self.private.as_ref()
+    }
+
+    pub fn proxy_revalidate(&self) -> bool {
+        self.proxy_revalidate
+    }
+
+    pub fn public(&self) -> bool {
+        self.public
+    }
+
+    pub fn s_maxage(&self) -> Option<DeltaSeconds> {
+        self.s_maxage
+    }
+
+    pub fn immutable(&self) -> bool {
+        self.immutable
+    }
+
+    pub fn stale_while_revalidate(&self) -> Option<DeltaSeconds> {
+        self.stale_while_revalidate
+    }
+
+    pub fn stale_if_error(&self) -> Option<DeltaSeconds> {
+        self.stale_if_error
+    }
+
+    /// Unknown directives, with their argument exactly as received.
+    pub fn other(&self) -> &[(&'a str, Option<&'a [u8]>)] {
+        &self.other
+    }
+
+    /// Which of [`DIRECTIVES`] are present, in the same order.
+    fn present(&self) -> [bool; DIRECTIVES.len()] {
+        [
+            self.max_age.is_some(),
+            self.max_stale.is_some(),
+            self.min_fresh.is_some(),
+            self.no_cache.is_some(),
+            self.no_store,
+            self.no_transform,
+            self.only_if_cached,
+            self.must_revalidate,
+            self.must_understand,
+            self.transform,
+            self.private.is_some(),
+            self.proxy_revalidate,
+            self.public,
+            self.s_maxage.is_some(),
+            self.immutable,
+            self.stale_while_revalidate.is_some(),
+            self.stale_if_error.is_some(),
+        ]
+    }
+
+    fn has(&self, directive: &str) -> bool {
+        DIRECTIVES
+            .iter()
+            .zip(self.present())
+            .any(|(&name, present)| present && name == directive)
     }
-    Ok(())
-}
 
-fn put_bool(ret: &mut BytesMut, v: &bool, s: &[u8]) {
-    if *v {
-        if !ret.is_empty() {
-            ret.put(&b", "[..]);
+    /// Copies the borrowed parts, giving the equivalent [`CacheControl`].
+    pub fn into_owned(self) -> CacheControl {
+        CacheControl {
+            max_age: self.max_age,
+            max_stale: self.max_stale,
+            min_fresh: self.min_fresh,
+            no_cache: self.no_cache.map(|f| f.to_vec()),
+            no_store: self.no_store,
+            no_transform: self.no_transform,
+            only_if_cached: self.only_if_cached,
+            must_revalidate: self.must_revalidate,
+            must_understand: self.must_understand,
+            transform: self.transform,
+            private: self.private.map(|f| f.to_vec()),
+            proxy_revalidate: self.proxy_revalidate,
+            public: self.public,
+            s_maxage: self.s_maxage,
+            immutable: self.immutable,
+            stale_while_revalidate: self.stale_while_revalidate,
+            stale_if_error: self.stale_if_error,
+            other: self
+                .other
+                .into_iter()
+                .map(|(name, argument)| (name.to_owned(), argument.map(<[u8]>::to_vec)))
+                .collect(),
         }
-        ret.put_slice(s);
+    }
+
+    /// Like `headers::Header::decode`, but reports why the value was rejected.
+    pub fn try_decode<I, V>(values: &mut I) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'a V>,
+        V: AsRef<[u8]> + ?Sized + 'a,
+    {
+        Self::decode_with(values, ParseMode::default(), &mut ())
+    }
+
+    /// Decodes following the given [`ParseMode`], reporting problems that do
+    /// not prevent decoding to `diagnostics`.
+    pub fn decode_with<I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'a V>,
+        V: AsRef<[u8]> + ?Sized + 'a,
+    {
+        decode_lines(
+            &mut values.map(|v| v.as_ref()),
+            Direction::Any,
+            mode,
+            diagnostics,
+        )
+    }
+
+    /// Like [`CacheControlRef::try_decode`], for field line values given as
+    /// byte slices, e.g. read from a log.
+    pub fn parse_field_lines(lines: &[&'a [u8]]) -> Result<Self, ParseError> {
+        decode_lines(
+            &mut lines.iter().copied(),
+            Direction::Any,
+            ParseMode::default(),
+            &mut (),
+        )
     }
 }
 
-fn put_u64(ret: &mut BytesMut, v: &Option<u64>, s: &[u8]) {
-    if let Some(v) = v {
-        if !ret.is_empty() {
-            ret.put(&b", "[..]);
-        }
-        ret.put_slice(s);
-        ret.put_u8(b'=');
-        ret.put(v.to_string().as_bytes());
+impl<'a> TryFrom<&'a [u8]> for CacheControlRef<'a> {
+    type Error = ParseError;
+
+    fn try_from(i: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::parse_field_lines(&[i])
     }
 }
 
-fn put_headerlist(ret: &mut BytesMut, v: &Option<Vec<HeaderName>>, s: &[u8]) {
-    if let Some(v) = v {
-        if !ret.is_empty() {
-            ret.put(&b", "[..]);
-        }
-        ret.put_slice(s);
-        if !v.is_empty() {
-            ret.put_u8(b'=');
-            ret.put_u8(b'"');
-            let mut first = true;
-            for h in v {
-                if !first {
-                    ret.put_u8(b',');
-                    first = false;
-                }
-                ret.put_slice(h.as_ref());
-            }
-            ret.put_u8(b'"');
-        }
+impl From<CacheControlRef<'_>> for CacheControl {
+    fn from(cc: CacheControlRef<'_>) -> Self {
+        cc.into_owned()
     }
 }
 
-fn put_other(ret: &mut BytesMut, v: &Vec<(String, Option<Vec<u8>>)>) {
-    for (h, p) in v {
-        if !ret.is_empty() {
-            ret.put(&b", "[..]);
-        }
-        ret.put(h.as_bytes());
-        if let Some(v) = p {
-            ret.put_u8(b'=');
-            ret.put(v.as_slice());
-        }
+/// The field names listed by `no-cache` or `private`, as a validated list of
+/// tokens.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct FieldNames<'a>(Cow<'a, [u8]>);
+
+impl<'a> FieldNames<'a> {
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        ListIter::from_bytes(&self.0).map(|e| core::str::from_utf8(e.value()).unwrap())
+    }
+
+    /// Whether the directive applies to the whole response.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// The field names, lowercase.
+    pub fn to_vec(&self) -> Vec<String> {
+        self.iter().map(|name| name.to_ascii_lowercase()).collect()
     }
 }
 
-impl Header for CacheControl {
-    fn name() -> &'static HeaderName {
-        &http::header::CACHE_CONTROL
+fn field_names<I>(fields: I) -> Vec<String>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    fields
+        .into_iter()
+        .map(|name| name.as_ref().to_ascii_lowercase())
+        .collect()
+}
+
+/// Fluent constructor for [`CacheControl`], created with [`CacheControl::builder`].
+#[derive(Clone, Default, Debug)]
+pub struct CacheControlBuilder {
+    cc: CacheControl,
+}
+
+impl CacheControlBuilder {
+    pub fn max_age(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.max_age = Some(seconds.into());
+        self
+    }
+
+    pub fn max_stale(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.max_stale = Some(Some(seconds.into()));
+        self
+    }
+
+    /// `max-stale` without an argument.
+    pub fn max_stale_any(mut self) -> Self {
+        self.cc.max_stale = Some(None);
+        self
+    }
+
+    pub fn min_fresh(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.min_fresh = Some(seconds.into());
+        self
+    }
+
+    pub fn no_cache(mut self) -> Self {
+        self.cc.no_cache = Some(Vec::new());
+        self
     }
 
-    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    /// `fields` are field names such as `HeaderName`s.
+    pub fn no_cache_fields<I>(mut self, fields: I) -> Self
     where
-        I: Iterator<Item = &'i HeaderValue>,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
     {
-        let mut ret = Self::default();
+        self.cc.no_cache = Some(field_names(fields));
+        self
+    }
 
-        while let Some(h) = values.next() {
-            process_header(&mut ret, h.as_bytes()).map_err(|_| headers::Error::invalid())?
-        }
-        // FIXME: if no tokens at all, error
-        Ok(ret)
+    pub fn no_store(mut self) -> Self {
+        self.cc.no_store = true;
+        self
+    }
+
+    pub fn no_transform(mut self) -> Self {
+        self.cc.no_transform = true;
+        self
+    }
+
+    pub fn only_if_cached(mut self) -> Self {
+        self.cc.only_if_cached = true;
+        self
+    }
+
+    pub fn must_revalidate(mut self) -> Self {
+        self.cc.must_revalidate = true;
+        self
+    }
+
+    pub fn must_understand(mut self) -> Self {
+        self.cc.must_understand = true;
+        self
+    }
+
+    pub fn transform(mut self) -> Self {
+        self.cc.transform = true;
+        self
+    }
+
+    pub fn private(mut self) -> Self {
+        self.cc.private = Some(Vec::new());
+        self
     }
 
-    fn encode<E>(&self, values: &mut E)
+    /// `fields` are field names such as `HeaderName`s.
+    pub fn private_fields<I>(mut self, fields: I) -> Self
     where
-        E: Extend<HeaderValue>,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
     {
-        let mut ret = BytesMut::new();
-        put_u64(&mut ret, &self.max_age, b"max-age");
-        put_u64(&mut ret, &self.max_stale, b"max-stale");
-        put_u64(&mut ret, &self.min_fresh, b"min-fresh");
-        put_headerlist(&mut ret, &self.no_cache, b"no-cache");
-        put_bool(&mut ret, &self.no_store, b"no-store");
-        put_bool(&mut ret, &self.no_transform, b"no-transform");
-        put_bool(&mut ret, &self.only_if_cached, b"only-if-cached");
-        put_bool(&mut ret, &self.must_revalidate, b"must-revalidate");
-        put_bool(&mut ret, &self.transform, b"transform");
-        put_headerlist(&mut ret, &self.private, b"private");
-        put_bool(&mut ret, &self.proxy_revalidate, b"proxy-revalidate");
-        put_bool(&mut ret, &self.public, b"public");
-        put_u64(&mut ret, &self.s_maxage, b"s-maxage");
-        put_bool(&mut ret, &self.immutable, b"immutable");
-        put_u64(
-            &mut ret,
-            &self.stale_while_revalidate,
-            b"stale-while-revalidate",
-        );
-        put_u64(&mut ret, &self.stale_if_error, b"stale-if-error");
-        put_other(&mut ret, &self.other);
-        if !ret.is_empty() {
-            let value = HeaderValue::from_maybe_shared(ret.freeze()).unwrap();
-            values.extend(std::iter::once(value));
-        }
+        self.cc.private = Some(field_names(fields));
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use headers::{HeaderMap, HeaderMapExt};
+    pub fn proxy_revalidate(mut self) -> Self {
+        self.cc.proxy_revalidate = true;
+        self
+    }
 
-    #[test]
-    fn test_header_list() {
-        assert_eq!(
-            header_list(b"foo,bar,bazzz"),
-            Ok(vec![
-                "foo".try_into().unwrap(),
-                "bar".try_into().unwrap(),
-                "bazzz".try_into().unwrap()
-            ])
-        );
-        assert_eq!(
-            header_list(b",,, , foo, bar, bazzz ,    , "),
-            Ok(vec![
-                "foo".try_into().unwrap(),
-                "bar".try_into().unwrap(),
-                "bazzz".try_into().unwrap()
-            ])
-        );
-        assert_eq!(header_list(b""), Ok(vec![]));
-        assert_eq!(header_list(b"foo bar"), Err(()));
+    pub fn public(mut self) -> Self {
+        self.cc.public = true;
+        self
     }
 
-    #[test]
-    fn it_works() {
-        let mut h = HeaderMap::new();
-        h.insert(
-            "Cache-Control",
-            HeaderValue::from_static(
-                r#",,, , , ,, ,,,,  no-store ,, , ,,zak     ="\\\"" ,, ,immutable     , ,     ,"#,
-            ),
-        );
-        let cc = h.typed_get::<CacheControl>().unwrap();
-        //assert_eq!(format!("{:?}", cc), "");
-        h.remove("Cache-Control");
-        h.typed_insert(cc);
-        assert_eq!(h["Cache-Control"], r#"no-store, immutable, zak="\\\"""#);
+    pub fn s_maxage(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.s_maxage = Some(seconds.into());
+        self
+    }
+
+    pub fn immutable(mut self) -> Self {
+        self.cc.immutable = true;
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.stale_while_revalidate = Some(seconds.into());
+        self
+    }
+
+    pub fn stale_if_error(mut self, seconds: impl Into<DeltaSeconds>) -> Self {
+        self.cc.stale_if_error = Some(seconds.into());
+        self
+    }
+
+    /// Adds an extension directive. `argument` is emitted verbatim if it is a
+    /// token or a quoted-string, and quoted otherwise.
+    pub fn other(mut self, name: impl Into<String>, argument: Option<Vec<u8>>) -> Self {
+        self.cc.other.push((name.into(), argument));
+        self
+    }
+
+    pub fn build(self) -> Result<CacheControl, BuildError> {
+        // `no-cache` with `max-age` is allowed, so skip the last pair.
+        for (a, b) in &CONFLICTS[..3] {
+            if self.cc.has(a) && self.cc.has(b) {
+                return Err(BuildError::Conflict(a, b));
+            }
+        }
+        self.build_with_conflicts()
+    }
+
+    /// Like [`CacheControlBuilder::build`], but allows conflicting
+    /// directives, which decoding keeps too.
+    pub(crate) fn build_with_conflicts(self) -> Result<CacheControl, BuildError> {
+        let cc = self.cc;
+        let fields = cc.no_cache.iter().chain(&cc.private).flatten();
+        if let Some(name) = fields.into_iter().find(|f| !is_token(f.as_bytes())) {
+            return Err(BuildError::InvalidFieldName(name.clone()));
+        }
+        for (name, argument) in &cc.other {
+            check_directive(name, argument.as_deref())?;
+        }
+        Ok(cc)
+    }
+}
+
+/// Checks that an extension directive can be encoded, and would be decoded
+/// as an extension again.
+fn check_directive(name: &str, argument: Option<&[u8]>) -> Result<(), BuildError> {
+    check_encodable(name, argument)?;
+    if DIRECTIVES.iter().any(|d| d.eq_ignore_ascii_case(name)) {
+        return Err(BuildError::KnownDirective(name.to_owned()));
+    }
+    Ok(())
+}
+
+/// Checks that a directive can be encoded.
+fn check_encodable(name: &str, argument: Option<&[u8]>) -> Result<(), BuildError> {
+    if !is_token(name.as_bytes()) {
+        return Err(BuildError::InvalidDirective(name.to_owned()));
+    }
+    if argument.is_some_and(|a| !can_quote(a)) {
+        return Err(BuildError::InvalidArgument(name.to_owned()));
+    }
+    Ok(())
+}
+
+/// Which messages the parsed directives may appear in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Any,
+    Request,
+    Response,
+}
+
+/// Directives defined only for requests in Section 5.2.1 of RFC9111.
+const REQUEST_ONLY: [&str; 3] = ["max-stale", "min-fresh", "only-if-cached"];
+
+/// Directives defined only for responses in Section 5.2.2 of RFC9111, RFC8246
+/// and RFC5861.
+const RESPONSE_ONLY: [&str; 8] = [
+    "must-revalidate",
+    "must-understand",
+    "private",
+    "proxy-revalidate",
+    "public",
+    "s-maxage",
+    "immutable",
+    "stale-while-revalidate",
+];
+
+impl Direction {
+    pub(crate) fn allows(self, directive: &str) -> bool {
+        match self {
+            Direction::Any => true,
+            Direction::Request => !RESPONSE_ONLY.contains(&directive),
+            Direction::Response => !REQUEST_ONLY.contains(&directive),
+        }
+    }
+
+    /// The first directive of `cc` not allowed in this direction.
+    fn check(self, cc: &CacheControl) -> Result<(), BuildError> {
+        // Only the response form of `no-cache` takes a list of fields.
+        if self == Direction::Request && cc.no_cache().is_some_and(|f| !f.is_empty()) {
+            return Err(BuildError::WrongDirection("no-cache"));
+        }
+        match REQUEST_ONLY
+            .iter()
+            .chain(RESPONSE_ONLY.iter())
+            .find(|&&name| cc.has(name) && !self.allows(name))
+        {
+            Some(name) => Err(BuildError::WrongDirection(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reason a [`CacheControlBuilder`] or [`RawDirective::new`] refused to build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// Two directives that contradict each other were both set.
+    Conflict(&'static str, &'static str),
+    /// An extension directive name is not a valid token.
+    InvalidDirective(String),
+    /// An extension directive is named after one of [`DIRECTIVES`], which
+    /// must be set with its own builder method.
+    KnownDirective(String),
+    /// The argument of the named directive contains control characters.
+    InvalidArgument(String),
+    /// A field name listed by `no-cache` or `private` is not a valid token.
+    InvalidFieldName(String),
+    /// The directive is not defined for the direction of the message, when
+    /// converting to a [`RequestCacheControl`] or [`ResponseCacheControl`].
+    WrongDirection(&'static str),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Conflict(a, b) => write!(f, "`{}` conflicts with `{}`", a, b),
+            BuildError::InvalidDirective(name) => write!(f, "invalid directive name {:?}", name),
+            BuildError::KnownDirective(name) => {
+                write!(f, "`{}` is not an extension directive", name)
+            }
+            BuildError::InvalidArgument(name) => write!(f, "invalid argument for `{}`", name),
+            BuildError::InvalidFieldName(name) => write!(f, "invalid field name {:?}", name),
+            BuildError::WrongDirection(name) => {
+                write!(f, "`{}` is not defined for this message direction", name)
+            }
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+#[derive(Logos, Clone, Debug, PartialEq)]
+#[logos(skip r"[ \t]+")]
+enum CCToken<'a> {
+    #[token(b"max-age", ignore(ascii_case))]
+    MaxAge,
+    #[token(b"max-stale", ignore(ascii_case))]
+    MaxStale,
+    #[token(b"min-fresh", ignore(ascii_case))]
+    MinFresh,
+    #[token(b"no-cache", ignore(ascii_case))]
+    NoCache,
+    #[token(b"no-store", ignore(ascii_case))]
+    NoStore,
+    #[token(b"no-transform", ignore(ascii_case))]
+    NoTransform,
+    #[token(b"only-if-cached", ignore(ascii_case))]
+    OnlyIfCached,
+    #[token(b"must-revalidate", ignore(ascii_case))]
+    MustRevalidate,
+    #[token(b"must-understand", ignore(ascii_case))]
+    MustUnderstand,
+    #[token(b"transform", ignore(ascii_case))]
+    Transform,
+    #[token(b"private", ignore(ascii_case))]
+    Private,
+    #[token(b"proxy-revalidate", ignore(ascii_case))]
+    ProxyRevalidate,
+    #[token(b"public", ignore(ascii_case))]
+    Public,
+    #[token(b"s-maxage", ignore(ascii_case))]
+    SMaxage,
+    #[token(b"immutable", ignore(ascii_case))]
+    Immutable,
+    #[token(b"stale-while-revalidate", ignore(ascii_case))]
+    StaleWhileRevalidate,
+    #[token(b"stale-if-error", ignore(ascii_case))]
+    StaleIfError,
+    #[regex(b"[!#$%&'*+.^_`|~0-9a-zA-Z-]+", |l| core::str::from_utf8(l.slice()).unwrap())]
+    Other(&'a str),
+    #[token(b"=")]
+    Equals,
+}
+
+#[derive(Logos, Debug, PartialEq)]
+#[logos(skip r"[ \t]+")]
+enum CCArg {
+    #[regex(b"[!#$%&'*+.^_`|~0-9a-zA-Z-]+")]
+    Token,
+    #[regex(br#""([^"\\\x00-\x08\x0a-\x1f\x7f]*)""#, priority = 2)]
+    Quoted,
+    #[regex(
+        br#""([^"\\\x00-\x08\x0a-\x1f\x7f]|\\[^\x00-\x08\x0a-\x1f\x7f])*""#,
+        priority = 1
+    )]
+    QuotedEscape,
+}
+
+/// Why the argument starting at `start` could not be read.
+fn argument_error(source: &[u8], start: usize) -> ParseError {
+    if source[start] != b'"' {
+        return ParseError::new(ParseErrorKind::InvalidToken, start);
+    }
+    // A quoted-string which would otherwise be complete can only have
+    // failed on a control character.
+    match source[start + 1..].iter().position(|&c| !is_quotable(c)) {
+        Some(pos) => ParseError::new(ParseErrorKind::InvalidToken, start + 1 + pos),
+        None => ParseError::new(ParseErrorKind::UnterminatedQuotedString, start),
+    }
+}
+
+fn header_list(i: Cow<'_, [u8]>) -> Result<FieldNames<'_>, ParseError> {
+    for e in ListIter::from_bytes(&i) {
+        let v = e.value();
+        match v.iter().position(|&c| !is_tchar(c)) {
+            None => {}
+            Some(pos) if is_wsp(v[pos]) => {
+                let next = pos + v[pos..].iter().take_while(|&&c| is_wsp(c)).count();
+                return Err(ParseError::new(
+                    ParseErrorKind::UnexpectedToken,
+                    e.offset() + next,
+                ));
+            }
+            Some(pos) => {
+                return Err(ParseError::new(
+                    ParseErrorKind::InvalidToken,
+                    e.offset() + pos,
+                ))
+            }
+        }
+    }
+    Ok(FieldNames(i))
+}
+
+impl CCToken<'_> {
+    fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            CCToken::MaxAge => "max-age",
+            CCToken::MaxStale => "max-stale",
+            CCToken::MinFresh => "min-fresh",
+            CCToken::NoCache => "no-cache",
+            CCToken::NoStore => "no-store",
+            CCToken::NoTransform => "no-transform",
+            CCToken::OnlyIfCached => "only-if-cached",
+            CCToken::MustRevalidate => "must-revalidate",
+            CCToken::MustUnderstand => "must-understand",
+            CCToken::Transform => "transform",
+            CCToken::Private => "private",
+            CCToken::ProxyRevalidate => "proxy-revalidate",
+            CCToken::Public => "public",
+            CCToken::SMaxage => "s-maxage",
+            CCToken::Immutable => "immutable",
+            CCToken::StaleWhileRevalidate => "stale-while-revalidate",
+            CCToken::StaleIfError => "stale-if-error",
+            CCToken::Other(_) | CCToken::Equals => return None,
+        })
+    }
+}
+
+/// Directives that contradict each other. The builder refuses them, while
+/// decoding only reports them.
+const CONFLICTS: [(&str, &str); 4] = [
+    ("public", "private"),
+    ("no-store", "immutable"),
+    ("transform", "no-transform"),
+    // Not strictly contradictory, as `no-cache` wins, but usually a mistake.
+    ("no-cache", "max-age"),
+];
+
+fn delta_seconds(ctx: &mut Context, v: &[u8], offset: usize) -> Result<DeltaSeconds, ParseError> {
+    let v = match ctx.mode {
+        ParseMode::Compat => leading_digits(v),
+        _ => v,
+    };
+    process_delta_seconds(ctx, v, offset)
+}
+
+/// Invalid directives are ignored unless parsing strictly.
+fn ignore_invalid(ctx: &mut Context, res: Result<(), ParseError>) -> Result<(), ParseError> {
+    match res {
+        Err(e) if ctx.mode == ParseMode::Strict => Err(e),
+        Err(e) => {
+            ctx.warn(DiagnosticKind::Ignored(e.kind()), e.offset());
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+/// `offset` is where the argument starts in the field line, or where the
+/// directive starts if there is no argument.
+fn process_directive<'a>(
+    ctx: &mut Context,
+    cc: &mut CacheControlRef<'a>,
+    direction: Direction,
+    directive: CCToken<'a>,
+    argument: Option<Cow<'a, [u8]>>,
+    raw_argument: Option<&'a [u8]>,
+    offset: usize,
+) -> Result<(), ParseError> {
+    let name = directive.name();
+    if name.is_some_and(|name| !direction.allows(name)) {
+        return Err(ParseError::new(ParseErrorKind::WrongDirection, offset));
+    }
+    let duplicate = name.is_some_and(|name| cc.has(name));
+    let list = |v| header_list(v).map_err(|e| e.shift(offset));
+    match (directive, argument) {
+        (CCToken::MaxAge, Some(v)) => cc.max_age = Some(delta_seconds(ctx, &v, offset)?),
+        (CCToken::MaxStale, Some(v)) => cc.max_stale = Some(Some(delta_seconds(ctx, &v, offset)?)),
+        (CCToken::MaxStale, None) => cc.max_stale = Some(None),
+        (CCToken::MinFresh, Some(v)) => cc.min_fresh = Some(delta_seconds(ctx, &v, offset)?),
+        (CCToken::NoCache, Some(v)) if direction != Direction::Request => {
+            cc.no_cache = Some(list(v)?)
+        }
+        (CCToken::NoCache, None) => cc.no_cache = Some(FieldNames::default()),
+        (CCToken::NoStore, None) => cc.no_store = true,
+        (CCToken::NoTransform, None) => cc.no_transform = true,
+        (CCToken::OnlyIfCached, None) => cc.only_if_cached = true,
+        (CCToken::MustRevalidate, None) => cc.must_revalidate = true,
+        (CCToken::MustUnderstand, None) => cc.must_understand = true,
+        (CCToken::Transform, None) => cc.transform = true,
+        (CCToken::Private, Some(v)) => cc.private = Some(list(v)?),
+        (CCToken::Private, None) => cc.private = Some(FieldNames::default()),
+        (CCToken::ProxyRevalidate, None) => cc.proxy_revalidate = true,
+        (CCToken::Public, None) => cc.public = true,
+        (CCToken::SMaxage, Some(v)) => cc.s_maxage = Some(delta_seconds(ctx, &v, offset)?),
+        (CCToken::Immutable, None) => cc.immutable = true,
+        (CCToken::StaleWhileRevalidate, Some(v)) => {
+            cc.stale_while_revalidate = Some(delta_seconds(ctx, &v, offset)?)
+        }
+        (CCToken::StaleIfError, Some(v)) => {
+            cc.stale_if_error = Some(delta_seconds(ctx, &v, offset)?)
+        }
+        (CCToken::Other(s), Some(_)) => cc.other.push((s, raw_argument)),
+        (CCToken::Other(s), None) => cc.other.push((s, None)),
+        (_, Some(_)) => return Err(ParseError::new(ParseErrorKind::UnexpectedArgument, offset)),
+        (_, None) => return Err(ParseError::new(ParseErrorKind::MissingArgument, offset)),
+    };
+    if let Some(name) = name {
+        if duplicate {
+            ctx.warn(DiagnosticKind::DuplicateDirective(name), offset);
+        }
+        for (a, b) in CONFLICTS {
+            let other = match name {
+                _ if name == a => b,
+                _ if name == b => a,
+                _ => continue,
+            };
+            if !duplicate && cc.has(other) {
+                ctx.warn(DiagnosticKind::ConflictingDirectives(name, other), offset);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn process_argument<'a>(
+    ctx: &mut Context,
+    cc: &mut CacheControlRef<'a>,
+    direction: Direction,
+    directive: CCToken<'a>,
+    l: logos::Lexer<'a, CCToken<'a>>,
+) -> Result<logos::Lexer<'a, CCToken<'a>>, ParseError> {
+    let mut subl = l.morph::<CCArg>();
+    let arg = match subl.next() {
+        Some(arg) => arg,
+        None => {
+            return Err(ParseError::new(
+                ParseErrorKind::MissingArgument,
+                subl.span().end,
+            ))
+        }
+    };
+    let start = subl.span().start;
+    let arg = arg.map_err(|_| argument_error(subl.source(), start))?;
+    let slice = subl.slice();
+    let res = match arg {
+        CCArg::Token => process_directive(
+            ctx,
+            cc,
+            direction,
+            directive,
+            Some(Cow::Borrowed(slice)),
+            Some(slice),
+            start,
+        ),
+        CCArg::Quoted => process_directive(
+            ctx,
+            cc,
+            direction,
+            directive,
+            Some(Cow::Borrowed(&slice[1..slice.len() - 1])),
+            Some(slice),
+            start + 1,
+        ),
+        CCArg::QuotedEscape => process_directive(
+            ctx,
+            cc,
+            direction,
+            directive,
+            Some(unquote(slice)),
+            Some(slice),
+            start + 1,
+        ),
+    };
+    ignore_invalid(ctx, res)?;
+    Ok(subl.morph::<CCToken>())
+}
+
+/// Processes one list member. With [`ParseMode::Compat`], a malformed
+/// member is reported and skipped.
+fn process_element<'a>(
+    ctx: &mut Context,
+    cc: &mut CacheControlRef<'a>,
+    direction: Direction,
+    e: &Element<'a>,
+) -> Result<(), ParseError> {
+    let mut l = CCToken::lexer(e.source());
+    l.bump(e.offset());
+    let mut cur = None;
+    let mut after_argument = false;
+    while let Some(t) = l.next() {
+        let start = l.span().start;
+        let res = match t {
+            Err(_) => Err(ParseError::new(ParseErrorKind::InvalidToken, start)),
+            Ok(CCToken::Equals) => match cur.take() {
+                Some((directive, _)) => {
+                    match process_argument(ctx, cc, direction, directive, l.clone()) {
+                        Ok(next) => {
+                            l = next;
+                            after_argument = true;
+                            continue;
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                None => Err(ParseError::new(ParseErrorKind::UnexpectedToken, start)),
+            },
+            Ok(_) if cur.is_some() || (after_argument && ctx.mode == ParseMode::Strict) => {
+                Err(ParseError::new(ParseErrorKind::UnexpectedToken, start))
+            }
+            Ok(directive) => {
+                cur = Some((directive, start));
+                after_argument = false;
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            if ctx.mode != ParseMode::Compat {
+                return Err(e);
+            }
+            ctx.warn(DiagnosticKind::Ignored(e.kind()), e.offset());
+            return Ok(());
+        }
+    }
+    if let Some((directive, offset)) = cur {
+        let res = process_directive(ctx, cc, direction, directive, None, None, offset);
+        ignore_invalid(ctx, res)?;
+    }
+    Ok(())
+}
+
+fn put_bool(w: &mut ListWriter, v: bool, s: &str) {
+    if v {
+        w.member(s);
+    }
+}
+
+fn put_delta_seconds(w: &mut ListWriter, v: Option<DeltaSeconds>, s: &str) {
+    if let Some(v) = v {
+        w.member(s).value(v.to_string());
+    }
+}
+
+fn put_headerlist(w: &mut ListWriter, v: &Option<Vec<String>>, s: &str) {
+    if let Some(v) = v {
+        w.member(s);
+        if !v.is_empty() {
+            w.quoted_list(v);
+        }
+    }
+}
+
+fn put_other(w: &mut ListWriter, v: &[(String, Option<Vec<u8>>)]) {
+    for (h, p) in v {
+        w.member(h);
+        if let Some(v) = p {
+            w.serialized(v);
+        }
+    }
+}
+
+impl CacheControl {
+    /// Like `headers::Header::decode`, but reports why the value was rejected.
+    pub fn try_decode<'i, I, V>(values: &mut I) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        Self::decode_with(values, ParseMode::default(), &mut ())
+    }
+
+    /// Decodes following the given [`ParseMode`], reporting problems that do
+    /// not prevent decoding to `diagnostics`.
+    pub fn decode_with<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        decode_lines(
+            &mut values.map(|v| v.as_ref()),
+            Direction::Any,
+            mode,
+            diagnostics,
+        )
+        .map(CacheControl::from)
+    }
+
+    /// Like [`CacheControl::try_decode`], for field line values given as byte
+    /// slices, e.g. read from a log.
+    pub fn parse_field_lines(lines: &[&[u8]]) -> Result<Self, ParseError> {
+        decode_lines(
+            &mut lines.iter().copied(),
+            Direction::Any,
+            ParseMode::default(),
+            &mut (),
+        )
+        .map(CacheControl::from)
+    }
+}
+
+impl_field_value!(CacheControl);
+
+fn decode_lines<'i, I>(
+    values: &mut I,
+    direction: Direction,
+    mode: ParseMode,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> Result<CacheControlRef<'i>, ParseError>
+where
+    I: Iterator<Item = &'i [u8]>,
+{
+    let mut ret = CacheControlRef::default();
+    let mut ctx = Context::new("cache-control", mode, diagnostics);
+
+    for e in ListIter::new(values) {
+        ctx.line = e.line();
+        process_element(&mut ctx, &mut ret, direction, &e)
+            .map_err(|err| err.at("cache-control", e.line()))?
+    }
+    // FIXME: if no tokens at all, error
+    Ok(ret)
+}
+
+impl CacheControl {
+    /// The encoded field lines, with the directives in a fixed order. There
+    /// are none if no directive is present.
+    pub fn to_field_lines(&self) -> Vec<Bytes> {
+        let mut w = ListWriter::new();
+        put_delta_seconds(&mut w, self.max_age, "max-age");
+        match self.max_stale {
+            Some(None) => put_bool(&mut w, true, "max-stale"),
+            v => put_delta_seconds(&mut w, v.flatten(), "max-stale"),
+        }
+        put_delta_seconds(&mut w, self.min_fresh, "min-fresh");
+        put_headerlist(&mut w, &self.no_cache, "no-cache");
+        put_bool(&mut w, self.no_store, "no-store");
+        put_bool(&mut w, self.no_transform, "no-transform");
+        put_bool(&mut w, self.only_if_cached, "only-if-cached");
+        put_bool(&mut w, self.must_revalidate, "must-revalidate");
+        put_bool(&mut w, self.must_understand, "must-understand");
+        put_bool(&mut w, self.transform, "transform");
+        put_headerlist(&mut w, &self.private, "private");
+        put_bool(&mut w, self.proxy_revalidate, "proxy-revalidate");
+        put_bool(&mut w, self.public, "public");
+        put_delta_seconds(&mut w, self.s_maxage, "s-maxage");
+        put_bool(&mut w, self.immutable, "immutable");
+        put_delta_seconds(
+            &mut w,
+            self.stale_while_revalidate,
+            "stale-while-revalidate",
+        );
+        put_delta_seconds(&mut w, self.stale_if_error, "stale-if-error");
+        put_other(&mut w, &self.other);
+        w.finish().into_iter().collect()
+    }
+}
+
+macro_rules! directional {
+    ($name:ident, $direction:expr) => {
+        impl $name {
+            pub fn as_cache_control(&self) -> &CacheControl {
+                &self.0
+            }
+
+            pub fn max_age(&self) -> Option<DeltaSeconds> {
+                self.0.max_age
+            }
+
+            pub fn no_store(&self) -> bool {
+                self.0.no_store
+            }
+
+            pub fn no_transform(&self) -> bool {
+                self.0.no_transform
+            }
+
+            pub fn stale_if_error(&self) -> Option<DeltaSeconds> {
+                self.0.stale_if_error
+            }
+
+            /// Unknown directives, with their argument exactly as received.
+            pub fn other(&self) -> &[(String, Option<Vec<u8>>)] {
+                &self.0.other
+            }
+
+            /// Like `headers::Header::decode`, but reports why the value was rejected.
+            pub fn try_decode<'i, I, V>(values: &mut I) -> Result<Self, ParseError>
+            where
+                I: Iterator<Item = &'i V>,
+                V: AsRef<[u8]> + ?Sized + 'i,
+            {
+                Self::decode_with(values, ParseMode::default(), &mut ())
+            }
+
+            /// Decodes following the given [`ParseMode`], reporting problems
+            /// that do not prevent decoding to `diagnostics`.
+            ///
+            /// Directives of the other direction are an error with
+            /// [`ParseMode::Strict`], and are otherwise dropped.
+            pub fn decode_with<'i, I, V>(
+                values: &mut I,
+                mode: ParseMode,
+                diagnostics: &mut dyn DiagnosticSink,
+            ) -> Result<Self, ParseError>
+            where
+                I: Iterator<Item = &'i V>,
+                V: AsRef<[u8]> + ?Sized + 'i,
+            {
+                decode_lines(
+                    &mut values.map(|v| v.as_ref()),
+                    $direction,
+                    mode,
+                    diagnostics,
+                )
+                .map(|cc| $name(cc.into()))
+            }
+
+            /// Like [`Self::try_decode`], for field line values given as
+            /// byte slices, e.g. read from a log.
+            pub fn parse_field_lines(lines: &[&[u8]]) -> Result<Self, ParseError> {
+                decode_lines(
+                    &mut lines.iter().copied(),
+                    $direction,
+                    ParseMode::default(),
+                    &mut (),
+                )
+                .map(|cc| $name(cc.into()))
+            }
+
+            /// See [`CacheControl::to_field_lines`].
+            pub fn to_field_lines(&self) -> Vec<Bytes> {
+                self.0.to_field_lines()
+            }
+        }
+
+        impl_field_value!($name);
+
+        impl AsRef<CacheControl> for $name {
+            fn as_ref(&self) -> &CacheControl {
+                &self.0
+            }
+        }
+
+        impl From<$name> for CacheControl {
+            fn from(cc: $name) -> Self {
+                cc.0
+            }
+        }
+
+        impl TryFrom<CacheControl> for $name {
+            type Error = BuildError;
+
+            fn try_from(cc: CacheControl) -> Result<Self, BuildError> {
+                $direction.check(&cc)?;
+                Ok($name(cc))
+            }
+        }
+    };
+}
+
+/// The Cache-Control field of a request, limited to the request directives.
+///
+/// Created by decoding, or by converting a [`CacheControl`] without response
+/// directives.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct RequestCacheControl(CacheControl);
+
+directional!(RequestCacheControl, Direction::Request);
+
+impl RequestCacheControl {
+    /// `Some(None)` if `max-stale` has no argument, meaning that a stale
+    /// response of any age is acceptable.
+    pub fn max_stale(&self) -> Option<Option<DeltaSeconds>> {
+        self.0.max_stale
+    }
+
+    pub fn min_fresh(&self) -> Option<DeltaSeconds> {
+        self.0.min_fresh
+    }
+
+    pub fn no_cache(&self) -> bool {
+        self.0.no_cache.is_some()
+    }
+
+    pub fn only_if_cached(&self) -> bool {
+        self.0.only_if_cached
+    }
+}
+
+/// The Cache-Control field of a response, limited to the response directives.
+///
+/// Created by decoding, or by converting a [`CacheControl`] without request
+/// directives.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct ResponseCacheControl(CacheControl);
+
+directional!(ResponseCacheControl, Direction::Response);
+
+impl ResponseCacheControl {
+    /// `Some` if `no-cache` is present; an empty slice means it applies to the
+    /// whole response rather than to the listed fields, which are lowercase.
+    pub fn no_cache(&self) -> Option<&[String]> {
+        self.0.no_cache.as_deref()
+    }
+
+    pub fn must_revalidate(&self) -> bool {
+        self.0.must_revalidate
+    }
+
+    pub fn must_understand(&self) -> bool {
+        self.0.must_understand
+    }
+
+    /// `Some` if `private` is present; an empty slice means it applies to the
+    /// whole response rather than to the listed fields, which are lowercase.
+    pub fn private(&self) -> Option<&[String]> {
+        self.0.private.as_deref()
+    }
+
+    pub fn proxy_revalidate(&self) -> bool {
+        self.0.proxy_revalidate
+    }
+
+    pub fn public(&self) -> bool {
+        self.0.public
+    }
+
+    pub fn s_maxage(&self) -> Option<DeltaSeconds> {
+        self.0.s_maxage
+    }
+
+    pub fn immutable(&self) -> bool {
+        self.0.immutable
+    }
+
+    pub fn stale_while_revalidate(&self) -> Option<DeltaSeconds> {
+        self.0.stale_while_revalidate
+    }
+}
+
+/// A single directive exactly as received, with its original casing and
+/// quoting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawDirective {
+    name: String,
+    argument: Option<Vec<u8>>,
+}
+
+impl RawDirective {
+    /// `argument` is emitted verbatim if it is a token or a quoted-string, and
+    /// quoted otherwise. Fails if `name` is not a token or `argument` contains
+    /// control characters.
+    pub fn new(name: impl Into<String>, argument: Option<Vec<u8>>) -> Result<Self, BuildError> {
+        let name = name.into();
+        check_encodable(&name, argument.as_deref())?;
+        Ok(RawDirective { name, argument })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this is the named directive, comparing case-insensitively.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// The argument as received, including the quotes of a quoted-string.
+    pub fn raw_argument(&self) -> Option<&[u8]> {
+        self.argument.as_deref()
+    }
+
+    /// The argument with the quoting of a quoted-string removed.
+    pub fn argument(&self) -> Option<Cow<'_, [u8]>> {
+        self.argument.as_deref().map(unquote)
+    }
+}
+
+/// Cache-Control as a list of directives, keeping their order, casing,
+/// quoting and duplicates.
+///
+/// Until the directives are modified, encoding emits the field lines exactly
+/// as they were decoded, so a proxy can forward them byte-for-byte. The
+/// meaning of the directives is available with
+/// [`RawCacheControl::cache_control`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RawCacheControl {
+    directives: Vec<RawDirective>,
+    lines: Option<Vec<Bytes>>,
+}
+
+impl RawCacheControl {
+    pub fn new(directives: Vec<RawDirective>) -> Self {
+        RawCacheControl {
+            directives,
+            lines: None,
+        }
+    }
+
+    pub fn directives(&self) -> &[RawDirective] {
+        &self.directives
+    }
+
+    /// Gives access to the directives, after which the field is encoded in
+    /// canonical form instead of as received.
+    pub fn directives_mut(&mut self) -> &mut Vec<RawDirective> {
+        self.lines = None;
+        &mut self.directives
+    }
+
+    /// Whether the directives were created or changed rather than decoded.
+    pub fn is_modified(&self) -> bool {
+        self.lines.is_none()
+    }
+
+    /// The directives separated by `", "`, without empty list elements or
+    /// optional whitespace.
+    pub fn canonical(&self) -> Bytes {
+        let mut w = ListWriter::new();
+        for d in &self.directives {
+            w.member(&d.name);
+            if let Some(v) = &d.argument {
+                w.serialized(v);
+            }
+        }
+        w.finish().unwrap_or_default()
+    }
+
+    /// Interprets the directives, reporting problems like
+    /// [`CacheControl::decode_with`].
+    pub fn cache_control(
+        &self,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<CacheControl, ParseError> {
+        match &self.lines {
+            Some(lines) => decode_lines(
+                &mut lines.iter().map(|v| &v[..]),
+                Direction::Any,
+                mode,
+                diagnostics,
+            )
+            .map(CacheControl::from),
+            None => decode_lines(
+                &mut core::iter::once(&self.canonical()[..]),
+                Direction::Any,
+                mode,
+                diagnostics,
+            )
+            .map(CacheControl::from),
+        }
+    }
+
+    /// Like `headers::Header::decode`, but reports why the value was rejected.
+    pub fn try_decode<'i, I, V>(values: &mut I) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        Self::decode_with(values, ParseMode::default(), &mut ())
+    }
+
+    /// Decodes following the given [`ParseMode`], reporting problems that do
+    /// not prevent decoding to `diagnostics`.
+    ///
+    /// Only the list syntax is checked; directive arguments are kept even if
+    /// [`CacheControl`] would reject or ignore them. Control characters other
+    /// than HTAB are rejected, so that the lines can be encoded unchanged.
+    pub fn decode_with<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        let mut ret = Self::default();
+        let mut ctx = Context::new("cache-control", mode, diagnostics);
+        let mut lines = Vec::new();
+        for (line, v) in values.enumerate() {
+            let v = v.as_ref();
+            if let Some(pos) = v
+                .iter()
+                .position(|&c| (c < b' ' && c != b'\t') || c == 0x7f)
+            {
+                return Err(
+                    ParseError::new(ParseErrorKind::InvalidToken, pos).at("cache-control", line)
+                );
+            }
+            lines.push(Bytes::copy_from_slice(v));
+        }
+
+        for e in ListIter::new(lines.iter().map(|v| &v[..])) {
+            ctx.line = e.line();
+            process_raw(&mut ctx, &mut ret.directives, &e)
+                .map_err(|err| err.at("cache-control", e.line()))?;
+        }
+        ret.lines = Some(lines);
+        Ok(ret)
+    }
+
+    /// Like [`RawCacheControl::try_decode`], for field line values given as
+    /// byte slices, e.g. read from a log.
+    pub fn parse_field_lines(lines: &[&[u8]]) -> Result<Self, ParseError> {
+        Self::try_decode(&mut lines.iter())
+    }
+}
+
+impl_field_value!(RawCacheControl);
+
+impl From<&CacheControl> for RawCacheControl {
+    fn from(cc: &CacheControl) -> Self {
+        let values = cc.to_field_lines();
+        let mut ret = Self::decode_with(&mut values.iter(), ParseMode::Strict, &mut ())
+            .expect("encoded Cache-Control is valid");
+        ret.lines = None;
+        ret
+    }
+}
+
+/// Reads the directives of a list member, checking only the list syntax.
+fn process_raw(
+    ctx: &mut Context,
+    directives: &mut Vec<RawDirective>,
+    e: &Element<'_>,
+) -> Result<(), ParseError> {
+    let mut l = CCToken::lexer(e.source());
+    l.bump(e.offset());
+    // Whether a directive name, or a name and its argument, were just read.
+    let mut after_name = false;
+    let mut after_argument = false;
+    while let Some(t) = l.next() {
+        let start = l.span().start;
+        let res = match t {
+            Err(_) => Err(ParseError::new(ParseErrorKind::InvalidToken, start)),
+            Ok(CCToken::Equals) if after_name => {
+                let mut subl = l.clone().morph::<CCArg>();
+                match subl.next() {
+                    Some(Ok(_)) => {
+                        let d = directives.last_mut().unwrap();
+                        d.argument = Some(subl.slice().to_vec());
+                        l = subl.morph();
+                        after_name = false;
+                        after_argument = true;
+                        continue;
+                    }
+                    Some(Err(_)) => Err(argument_error(e.source(), subl.span().start)),
+                    None => Err(ParseError::new(
+                        ParseErrorKind::MissingArgument,
+                        subl.span().end,
+                    )),
+                }
+            }
+            Ok(CCToken::Equals) => Err(ParseError::new(ParseErrorKind::UnexpectedToken, start)),
+            Ok(_) if after_name || (after_argument && ctx.mode == ParseMode::Strict) => {
+                Err(ParseError::new(ParseErrorKind::UnexpectedToken, start))
+            }
+            Ok(_) => {
+                let name = core::str::from_utf8(l.slice()).unwrap();
+                directives.push(RawDirective {
+                    name: name.to_owned(),
+                    argument: None,
+                });
+                after_name = true;
+                after_argument = false;
+                Ok(())
+            }
+        };
+        if let Err(e) = res {
+            if ctx.mode != ParseMode::Compat {
+                return Err(e);
+            }
+            ctx.warn(DiagnosticKind::Ignored(e.kind()), e.offset());
+            // Drop the directive the malformed member started with.
+            if after_name || after_argument {
+                directives.pop();
+            }
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+impl RawCacheControl {
+    /// The field lines as decoded, or the canonical form if the directives
+    /// were modified. There are none if there are no directives.
+    pub fn to_field_lines(&self) -> Vec<Bytes> {
+        match &self.lines {
+            Some(lines) => lines.clone(),
+            None if self.directives.is_empty() => Vec::new(),
+            None => vec![self.canonical()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostics;
+    use std::str::FromStr;
+
+    fn secs(seconds: u64) -> Option<DeltaSeconds> {
+        Some(DeltaSeconds::new(seconds))
+    }
+
+    #[test]
+    fn test_header_list() {
+        let header_list = |v: &'static [u8]| header_list(Cow::Borrowed(v)).map(|f| f.to_vec());
+        assert_eq!(
+            header_list(b"foo,bar,bazzz"),
+            Ok(vec!["foo".to_owned(), "bar".to_owned(), "bazzz".to_owned()])
+        );
+        assert_eq!(
+            header_list(b",,, , foo, bar, bazzz ,    , "),
+            Ok(vec!["foo".to_owned(), "bar".to_owned(), "bazzz".to_owned()])
+        );
+        assert_eq!(header_list(b""), Ok(vec![]));
+        assert_eq!(
+            header_list(b"foo bar"),
+            Err(ParseError::new(ParseErrorKind::UnexpectedToken, 4))
+        );
+    }
+
+    #[test]
+    fn it_works() {
+        let cc: CacheControl =
+            r#",,, , , ,, ,,,,  no-store ,, , ,,zak     ="\\\"" ,, ,immutable     , ,     ,"#
+                .parse()
+                .unwrap();
+        //assert_eq!(format!("{:?}", cc), "");
+        assert_eq!(cc.to_string(), r#"no-store, immutable, zak="\\\"""#);
+    }
+
+    #[test]
+    fn test_try_decode() {
+        let try_decode = |vals: &[&'static str]| {
+            CacheControl::try_decode(&mut vals.iter())
+                .map_err(|e| (e.header(), e.kind(), e.line(), e.offset()))
+        };
+
+        assert!(try_decode(&["max-age=5", "no-store"]).is_ok());
+        assert_eq!(
+            try_decode(&["max-age=5", "no-store s-maxage=5"]),
+            Err(("cache-control", ParseErrorKind::UnexpectedToken, 1, 9))
+        );
+        assert_eq!(
+            try_decode(&["zak=\"abc"]),
+            Err((
+                "cache-control",
+                ParseErrorKind::UnterminatedQuotedString,
+                0,
+                4
+            ))
+        );
+        assert_eq!(
+            try_decode(&["zak=\"a\x01b\""]),
+            Err(("cache-control", ParseErrorKind::InvalidToken, 0, 6))
+        );
+        assert_eq!(
+            try_decode(&["zak=\"a\\\x01b\""]),
+            Err(("cache-control", ParseErrorKind::InvalidToken, 0, 7))
+        );
+        assert_eq!(
+            try_decode(&["max-age="]),
+            Err(("cache-control", ParseErrorKind::MissingArgument, 0, 8))
+        );
+        assert_eq!(
+            try_decode(&["=5"]),
+            Err(("cache-control", ParseErrorKind::UnexpectedToken, 0, 0))
+        );
+        assert_eq!(
+            try_decode(&["max-age=5;"]),
+            Err(("cache-control", ParseErrorKind::InvalidToken, 0, 9))
+        );
+    }
+
+    #[test]
+    fn test_decode_with() {
+        let decode = |mode, val: &'static str| {
+            CacheControl::decode_with(&mut std::iter::once(val), mode, &mut ())
+                .map_err(|e| (e.kind(), e.offset()))
+        };
+
+        let cc = decode(ParseMode::Lenient, "max-age=abc, no-store=1, no-cache").unwrap();
+        assert_eq!(cc.max_age(), None);
+        assert!(!cc.no_store());
+        assert_eq!(cc.no_cache(), Some(&[][..]));
+        assert_eq!(
+            decode(ParseMode::Strict, "max-age=abc, no-cache"),
+            Err((ParseErrorKind::InvalidInteger, 8))
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, "no-cache, no-store=1"),
+            Err((ParseErrorKind::UnexpectedArgument, 19))
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, "no-cache, max-age"),
+            Err((ParseErrorKind::MissingArgument, 10))
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, "max-age=5 no-store"),
+            Err((ParseErrorKind::UnexpectedToken, 10))
+        );
+        assert!(decode(ParseMode::Lenient, "max-age=5 no-store").is_ok());
+        assert_eq!(
+            decode(ParseMode::Lenient, "max-age=5;, no-store"),
+            Err((ParseErrorKind::InvalidToken, 9))
+        );
+
+        let cc = decode(ParseMode::Compat, "max-age=5;x, no-store, a b c, =").unwrap();
+        assert_eq!(cc.max_age(), secs(5));
+        assert!(cc.no_store());
+        assert!(cc.other().is_empty());
+        let cc = decode(ParseMode::Compat, "max-age=60s, s-maxage=\"30\"").unwrap();
+        assert_eq!(cc.max_age(), secs(60));
+        assert_eq!(cc.s_maxage(), secs(30));
+
+        // Quoted-pairs are unescaped before the argument is validated.
+        let cc = decode(ParseMode::Strict, r#"no-cache="a\-b", max-age="6\0""#).unwrap();
+        assert_eq!(cc.no_cache(), Some(&["a-b".to_owned()][..]));
+        assert_eq!(cc.max_age(), secs(60));
+        assert!(decode(ParseMode::Strict, r#"no-cache="a\\b""#).is_err());
+        let cc = decode(ParseMode::Lenient, r#"no-cache="a\\b", private="a\\b""#).unwrap();
+        assert_eq!(cc.no_cache(), None);
+        assert_eq!(cc.private(), None);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let decode = |vals: &[&'static str]| {
+            let mut diagnostics = Diagnostics::new();
+            let cc =
+                CacheControl::decode_with(&mut vals.iter(), ParseMode::Lenient, &mut diagnostics)
+                    .unwrap();
+            let found: Vec<_> = diagnostics
+                .iter()
+                .map(|d| (d.kind(), d.line(), d.offset()))
+                .collect();
+            (cc, found)
+        };
+
+        let (cc, found) = decode(&["max-age=5", "max-age=10"]);
+        assert_eq!(cc.max_age(), secs(10));
+        assert_eq!(
+            found,
+            [(DiagnosticKind::DuplicateDirective("max-age"), 1, 8)]
+        );
+        let (_, found) = decode(&["no-cache, max-age=005"]);
+        assert_eq!(
+            found,
+            [
+                (DiagnosticKind::LeadingZeros, 0, 18),
+                (
+                    DiagnosticKind::ConflictingDirectives("max-age", "no-cache"),
+                    0,
+                    18
+                )
+            ]
+        );
+        let (_, found) = decode(&["max-age=x, no-store"]);
+        assert_eq!(
+            found,
+            [(
+                DiagnosticKind::Ignored(ParseErrorKind::InvalidInteger),
+                0,
+                8
+            )]
+        );
+        let (_, found) = decode(&["max-age=0, no-store, zak, zak"]);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_process_directive() {
+        let mut cc = CacheControlRef::default();
+        let directive = |cc: &mut CacheControlRef<'static>, d, arg: Option<&'static [u8]>| {
+            let mut sink = ();
+            let mut ctx = Context::new("cache-control", ParseMode::Strict, &mut sink);
+            process_directive(
+                &mut ctx,
+                cc,
+                Direction::Any,
+                d,
+                arg.map(Cow::Borrowed),
+                arg,
+                8,
+            )
+            .map_err(|e| (e.kind(), e.offset()))
+        };
+        assert_eq!(
+            directive(&mut cc, CCToken::MaxAge, Some(b"12x")),
+            Err((ParseErrorKind::InvalidInteger, 10))
+        );
+        assert_eq!(
+            directive(&mut cc, CCToken::MaxAge, None),
+            Err((ParseErrorKind::MissingArgument, 8))
+        );
+        assert_eq!(
+            directive(&mut cc, CCToken::NoStore, Some(b"1")),
+            Err((ParseErrorKind::UnexpectedArgument, 8))
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let cc: CacheControl = "max-age=60, no-cache, private=\"set-cookie\", zak"
+            .parse()
+            .unwrap();
+        assert_eq!(cc.max_age(), secs(60));
+        assert_eq!(cc.no_cache(), Some(&[][..]));
+        assert_eq!(cc.private(), Some(&["set-cookie".to_owned()][..]));
+        assert!(!cc.public());
+        assert_eq!(cc.other(), &[("zak".to_owned(), None)]);
+    }
+
+    #[test]
+    fn test_parse() {
+        let cc: CacheControl = "max-age=60, private=\"a\"".parse().unwrap();
+        assert_eq!(cc.max_age(), secs(60));
+        assert_eq!(cc.to_string(), "max-age=60, private=\"a\"");
+        assert_eq!(
+            CacheControl::parse_field_lines(&[b"no-store", b"max-age=5"]).unwrap(),
+            CacheControl::builder()
+                .no_store()
+                .max_age(5)
+                .build()
+                .unwrap()
+        );
+        assert_eq!(
+            CacheControl::try_from(&b"max-age=5"[..]).unwrap().max_age(),
+            secs(5)
+        );
+        let cc = CacheControlRef::try_from(&b"max-age=5, zak=1"[..]).unwrap();
+        assert_eq!(cc.other(), &[("zak", Some(&b"1"[..]))]);
+
+        let req = RequestCacheControl::from_str("max-age=5, public").unwrap();
+        assert_eq!(req.to_string(), "max-age=5");
+        assert!(ResponseCacheControl::parse_field_lines(&[b"s-maxage=1"]).is_ok());
+
+        let raw = RawCacheControl::parse_field_lines(&[b"Max-Age=5", b"zak"]).unwrap();
+        assert_eq!(raw.to_string(), "Max-Age=5, zak");
+        assert_eq!(
+            RawCacheControl::parse_field_lines(&[b"a", b"b\x01"]).map_err(|e| (
+                e.kind(),
+                e.line(),
+                e.offset()
+            )),
+            Err((ParseErrorKind::InvalidToken, 1, 1))
+        );
+    }
+
+    #[test]
+    fn test_ref() {
+        let vals = [
+            "max-age=60, private=\"set-cookie, a\"",
+            "no-cache=\"x-\\y\", zak=\"1\", Foo",
+        ];
+        let cc = CacheControlRef::try_decode(&mut vals.iter()).unwrap();
+        assert_eq!(cc.max_age(), secs(60));
+        let private = cc.private().unwrap();
+        assert_eq!(private.iter().collect::<Vec<_>>(), ["set-cookie", "a"]);
+        assert!(matches!(private.0, Cow::Borrowed(_)));
+        let no_cache = cc.no_cache().unwrap();
+        assert_eq!(no_cache.iter().collect::<Vec<_>>(), ["x-y"]);
+        assert!(matches!(no_cache.0, Cow::Owned(_)));
+        assert_eq!(cc.other(), &[("zak", Some(&b"\"1\""[..])), ("Foo", None)]);
+
+        let owned = cc.into_owned();
+        assert_eq!(owned, CacheControl::try_decode(&mut vals.iter()).unwrap());
+        assert_eq!(
+            owned.private(),
+            Some(&["set-cookie".to_owned(), "a".to_owned()][..])
+        );
+        assert!(CacheControlRef::try_decode(&mut std::iter::once(&vals[0]))
+            .unwrap()
+            .no_cache()
+            .is_none());
+    }
+
+    #[test]
+    fn test_directions() {
+        let decode = |mode, val: &'static str| {
+            let mut diagnostics = Diagnostics::new();
+            let req =
+                RequestCacheControl::decode_with(&mut std::iter::once(val), mode, &mut diagnostics);
+            let resp = ResponseCacheControl::decode_with(
+                &mut std::iter::once(val),
+                mode,
+                &mut Diagnostics::new(),
+            );
+            (
+                req.map_err(|e| (e.kind(), e.offset())),
+                resp.map_err(|e| (e.kind(), e.offset())),
+                diagnostics.iter().map(|d| d.kind()).collect::<Vec<_>>(),
+            )
+        };
+
+        let (req, resp, diagnostics) = decode(ParseMode::Lenient, "max-age=5, public");
+        let req = req.unwrap();
+        assert_eq!(req.max_age(), secs(5));
+        assert!(!req.as_cache_control().public());
+        assert!(resp.unwrap().public());
+        assert_eq!(
+            diagnostics,
+            [DiagnosticKind::Ignored(ParseErrorKind::WrongDirection)]
+        );
+
+        let (req, resp, _) = decode(ParseMode::Strict, "only-if-cached");
+        assert!(req.unwrap().only_if_cached());
+        assert_eq!(resp, Err((ParseErrorKind::WrongDirection, 0)));
+
+        let (req, resp, _) = decode(ParseMode::Strict, "no-cache=\"set-cookie\"");
+        assert_eq!(req, Err((ParseErrorKind::UnexpectedArgument, 10)));
+        assert_eq!(
+            resp.unwrap().no_cache(),
+            Some(&["set-cookie".to_owned()][..])
+        );
+
+        let (req, resp, _) = decode(ParseMode::Strict, "max-stale, no-store, x");
+        assert_eq!(req.unwrap().max_stale(), Some(None));
+        assert_eq!(resp, Err((ParseErrorKind::WrongDirection, 0)));
+
+        let cc = CacheControl::builder().public().build().unwrap();
+        assert_eq!(
+            RequestCacheControl::try_from(cc.clone()),
+            Err(BuildError::WrongDirection("public"))
+        );
+        let resp = ResponseCacheControl::try_from(cc.clone()).unwrap();
+        assert_eq!(CacheControl::from(resp), cc);
+    }
+
+    #[test]
+    fn test_merge() {
+        let decode = |val: &str| val.parse::<CacheControl>().unwrap();
+        let merge = |a, b| decode(a).merge(&decode(b)).to_string();
+
+        assert_eq!(
+            merge("max-age=60, s-maxage=600", "max-age=300, s-maxage=30"),
+            "max-age=60, s-maxage=30"
+        );
+        assert_eq!(
+            merge("public, max-age=60", "no-store, must-revalidate"),
+            "max-age=60, no-store, must-revalidate"
+        );
+        assert_eq!(
+            merge("public, immutable, max-age=60", "public, immutable"),
+            "max-age=60, public, immutable"
+        );
+        let merged =
+            decode("private=\"a\", no-cache=\"c\"").merge(&decode("private=\"b\", no-cache"));
+        assert_eq!(merged.no_cache(), Some(&[][..]));
+        assert_eq!(
+            merged.private(),
+            Some(&["a".to_owned(), "b".to_owned()][..])
+        );
+        assert_eq!(merge("max-stale", "max-stale=10"), "max-stale=10");
+        assert_eq!(merge("max-stale", "max-stale"), "max-stale");
+        assert_eq!(merge("max-stale=20", "max-stale=10"), "max-stale=10");
+        assert_eq!(merge("max-stale", "max-age=5"), "max-age=5");
+        assert_eq!(
+            merge(
+                "stale-while-revalidate=30, stale-if-error=100",
+                "stale-while-revalidate=60, stale-if-error=10"
+            ),
+            "stale-while-revalidate=30, stale-if-error=10"
+        );
+        assert_eq!(merge("stale-while-revalidate=30", "stale-if-error=10"), "");
+        assert_eq!(merge("no-store, must-understand", "no-store"), "no-store");
+        assert_eq!(
+            merge("no-store, must-understand", "must-understand, no-store"),
+            "no-store, must-understand"
+        );
+        assert_eq!(merge("min-fresh=20", "min-fresh=10"), "min-fresh=20");
+        assert_eq!(merge("foo=1", "foo=2, bar"), "foo=1, bar");
+
+        let mut cc = decode("max-age=60");
+        cc.restrict(&CacheControl::default());
+        assert_eq!(cc, decode("max-age=60"));
+        let mut cc = decode("max-stale=60, stale-if-error=100");
+        cc.restrict(&CacheControl::default());
+        assert_eq!(cc, CacheControl::default());
+        let mut cc = CacheControl::default();
+        cc.restrict(&decode("max-stale, stale-while-revalidate=5"));
+        assert_eq!(cc, CacheControl::default());
+    }
+
+    #[test]
+    fn test_raw() {
+        let raw = |vals: &[&'static str]| RawCacheControl::try_decode(&mut vals.iter()).unwrap();
+        let encode = |raw: &RawCacheControl| raw.to_field_lines();
+
+        let lines = ["Max-Age=60 ,, private=\"a, b\"", "max-age=5,zak=\"\\x\""];
+        let mut cc = raw(&lines);
+        assert!(!cc.is_modified());
+        assert_eq!(encode(&cc), lines);
+        assert_eq!(
+            cc.directives()
+                .iter()
+                .map(|d| (d.name(), d.raw_argument()))
+                .collect::<Vec<_>>(),
+            [
+                ("Max-Age", Some(&b"60"[..])),
+                ("private", Some(&b"\"a, b\""[..])),
+                ("max-age", Some(&b"5"[..])),
+                ("zak", Some(&b"\"\\x\""[..])),
+            ]
+        );
+        assert_eq!(cc.directives()[1].argument().unwrap(), &b"a, b"[..]);
+        assert_eq!(cc.directives()[3].argument().unwrap(), &b"x"[..]);
+        assert!(cc.directives()[0].is("max-age"));
+        assert_eq!(
+            cc.cache_control(ParseMode::Lenient, &mut ())
+                .unwrap()
+                .max_age(),
+            secs(5)
+        );
+
+        cc.directives_mut().retain(|d| !d.is("max-age"));
+        assert!(cc.is_modified());
+        assert_eq!(encode(&cc), ["private=\"a, b\", zak=\"\\x\""]);
+        cc.directives_mut().clear();
+        assert!(encode(&cc).is_empty());
+        cc.directives_mut()
+            .push(RawDirective::new("zak", Some(b"a b".to_vec())).unwrap());
+        assert_eq!(encode(&cc), ["zak=\"a b\""]);
+        assert!(RawDirective::new("Max-Age", Some(b"5".to_vec())).is_ok());
+        assert_eq!(
+            RawDirective::new("a b", None),
+            Err(BuildError::InvalidDirective("a b".to_owned()))
+        );
+        assert_eq!(
+            RawDirective::new("zak", Some(b"\x7f".to_vec())),
+            Err(BuildError::InvalidArgument("zak".to_owned()))
+        );
+
+        let cc = CacheControl::builder()
+            .must_understand()
+            .no_store()
+            .build()
+            .unwrap();
+        assert_eq!(
+            encode(&RawCacheControl::from(&cc)),
+            ["no-store, must-understand"]
+        );
+    }
+
+    #[test]
+    fn test_raw_errors() {
+        let decode = |mode, val: &'static str| {
+            RawCacheControl::decode_with(&mut std::iter::once(val), mode, &mut ())
+                .map(|cc| cc.directives().len())
+                .map_err(|e| (e.kind(), e.offset()))
+        };
+
+        assert_eq!(
+            decode(ParseMode::Lenient, "a=\"b"),
+            Err((ParseErrorKind::UnterminatedQuotedString, 2))
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, "a="),
+            Err((ParseErrorKind::MissingArgument, 2))
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, "a b"),
+            Err((ParseErrorKind::UnexpectedToken, 2))
+        );
+        assert_eq!(decode(ParseMode::Lenient, "a=1 b"), Ok(2));
+        assert_eq!(
+            decode(ParseMode::Strict, "a=1 b"),
+            Err((ParseErrorKind::UnexpectedToken, 4))
+        );
+        assert_eq!(decode(ParseMode::Compat, "a, b=\"c, d, e=1"), Ok(1));
+        assert_eq!(decode(ParseMode::Compat, "a=\"b, c\", d=1"), Ok(2));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let cc: CacheControl = "Max-Age=5, NO-STORE, Must-Understand".parse().unwrap();
+        assert_eq!(cc.max_age(), secs(5));
+        assert!(cc.no_store());
+        assert!(cc.must_understand());
+        assert_eq!(cc.to_string(), "max-age=5, no-store, must-understand");
+    }
+
+    #[test]
+    fn test_encode() {
+        let encode = |cc: CacheControl| cc.to_string();
+
+        assert_eq!(
+            encode(
+                CacheControl::builder()
+                    .no_cache_fields(["a", "b", "C"])
+                    .private_fields(["d"])
+                    .build()
+                    .unwrap()
+            ),
+            "no-cache=\"a,b,c\", private=\"d\""
+        );
+        assert_eq!(
+            encode(
+                CacheControl::builder()
+                    .other("a", Some(b"x y".to_vec()))
+                    .other("b", Some(b"\"x\\\"\"".to_vec()))
+                    .other("c", Some(b"\"x".to_vec()))
+                    .build()
+                    .unwrap()
+            ),
+            "a=\"x y\", b=\"x\\\"\", c=\"\\\"x\""
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let cc: CacheControl =
+            "max-age=2147483647, s-maxage=2147483648, max-stale=99999999999999999999"
+                .parse()
+                .unwrap();
+        assert_eq!(cc.max_age(), secs(2147483647));
+        assert_eq!(cc.s_maxage(), Some(DeltaSeconds::OVERFLOW));
+        assert_eq!(cc.max_stale(), Some(Some(DeltaSeconds::OVERFLOW)));
+        assert_eq!(
+            cc.to_string(),
+            "max-age=2147483647, max-stale=2147483648, s-maxage=2147483648"
+        );
+    }
+
+    #[test]
+    fn test_max_stale() {
+        let roundtrip = |val: &str| {
+            let cc: CacheControl = val.parse().unwrap();
+            (cc.max_stale(), cc.to_string())
+        };
+
+        assert_eq!(roundtrip("max-stale"), (Some(None), "max-stale".to_owned()));
+        assert_eq!(
+            roundtrip("max-stale=10"),
+            (Some(Some(DeltaSeconds::new(10))), "max-stale=10".to_owned())
+        );
+        assert_eq!(
+            CacheControl::builder()
+                .max_stale_any()
+                .build()
+                .unwrap()
+                .max_stale(),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let cc = CacheControl::builder()
+            .public()
+            .max_age(60)
+            .immutable()
+            .build()
+            .unwrap();
+        assert!(cc.public());
+        assert_eq!(cc.max_age(), secs(60));
+        assert!(cc.immutable());
+
+        let cc = CacheControl::builder()
+            .max_age(std::time::Duration::from_secs(90))
+            .s_maxage(u64::MAX)
+            .build()
+            .unwrap();
+        assert_eq!(cc.max_age(), secs(90));
+        assert_eq!(cc.s_maxage(), Some(DeltaSeconds::OVERFLOW));
+
+        assert_eq!(
+            CacheControl::builder().public().private().build(),
+            Err(BuildError::Conflict("public", "private"))
+        );
+        assert_eq!(
+            CacheControl::builder().no_store().immutable().build(),
+            Err(BuildError::Conflict("no-store", "immutable"))
+        );
+        assert_eq!(
+            CacheControl::builder().other("a b", None).build(),
+            Err(BuildError::InvalidDirective("a b".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder().other("", None).build(),
+            Err(BuildError::InvalidDirective("".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder().other("No-Store", None).build(),
+            Err(BuildError::KnownDirective("No-Store".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder()
+                .other("max-age", Some(b"abc".to_vec()))
+                .build(),
+            Err(BuildError::KnownDirective("max-age".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder()
+                .other("a", Some(b"b\r\nc".to_vec()))
+                .build(),
+            Err(BuildError::InvalidArgument("a".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder()
+                .private_fields(["set-cookie", "a,b"])
+                .build(),
+            Err(BuildError::InvalidFieldName("a,b".to_owned()))
+        );
+        assert_eq!(
+            CacheControl::builder().no_cache_fields([""]).build(),
+            Err(BuildError::InvalidFieldName("".to_owned()))
+        );
     }
 }
@@ -0,0 +1,921 @@
+//! HTTP-date parsing and formatting
+//!
+//! ### RFC9110 5.6.7. Date/Time Formats
+//!
+//! ```text
+//!   HTTP-date    = IMF-fixdate / obs-date
+//!
+//!   IMF-fixdate  = day-name "," SP date1 SP time-of-day SP GMT
+//!   ; fixed length/zone/capitalization subset of the format
+//!   ; see Section 3.3 of [RFC5322]
+//!
+//!   day-name     = %s"Mon" / %s"Tue" / %s"Wed"
+//!                / %s"Thu" / %s"Fri" / %s"Sat" / %s"Sun"
+//!
+//!   date1        = day SP month SP year
+//!                ; e.g., 02 Jun 1982
+//!
+//!   time-of-day  = hour ":" minute ":" second
+//!                ; 00:00:00 - 23:59:60 (leap second)
+//!
+//!   obs-date     = rfc850-date / asctime-date
+//!
+//!   rfc850-date  = day-name-l "," SP date2 SP time-of-day SP GMT
+//!   date2        = day "-" month "-" 2DIGIT
+//!                ; e.g., 02-Jun-82
+//!
+//!   asctime-date = day-name SP date3 SP time-of-day SP year
+//!   date3        = month SP ( 2DIGIT / ( SP DIGIT ))
+//!                ; e.g., Jun  2
+//! ```
+//!
+//! Recipients of a timestamp value in rfc850-date format, which uses a
+//! two-digit year, MUST interpret a timestamp that appears to be more than 50
+//! years in the future as representing the most recent year in the past that
+//! had the same last two digits.
+//!
+//! HTTP-date is case sensitive. Note that Section 4.2 of [CACHING] relaxes
+//! this for cache recipients.
+//!
+//! ### Leap seconds
+//!
+//! A second of `60` is only accepted at `23:59:60`, where leap seconds are
+//! inserted, and is read as `23:59:59` since [`HttpDate`], like Unix time and
+//! `SystemTime`, has no room for it.
+
+use core::fmt;
+use core::time::Duration;
+
+use logos::{Lexer, Logos};
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::mode::ParseMode;
+use crate::util::{parse_u16, parse_u8};
+
+const DAY_NAMES: [&[u8; 3]; 7] = [b"Mon", b"Tue", b"Wed", b"Thu", b"Fri", b"Sat", b"Sun"];
+
+const LONG_DAY_NAMES: [&[u8]; 7] = [
+    b"Monday",
+    b"Tuesday",
+    b"Wednesday",
+    b"Thursday",
+    b"Friday",
+    b"Saturday",
+    b"Sunday",
+];
+
+const MONTHS: [&[u8; 3]; 12] = [
+    b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun", b"Jul", b"Aug", b"Sep", b"Oct", b"Nov", b"Dec",
+];
+
+/// The year two-digit rfc850-date years are resolved against when there is
+/// no clock to read, i.e. without the `std` feature.
+///
+/// This is only a fallback, which goes out of date: without `std`, pass the
+/// current year to [`HttpDate::parse_with_year`] or
+/// [`Expires::decode_with_year`] instead.
+///
+/// [`Expires::decode_with_year`]: crate::expires::Expires::decode_with_year
+pub const NO_CLOCK_YEAR: u16 = 2026;
+
+/// A date and time in GMT with one second precision, between the years 0 and
+/// 9999 of the proleptic Gregorian calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+/// Which of the three HTTP-date formats a date was in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateFormat {
+    ImfFixdate,
+    Rfc850,
+    Asctime,
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the given date.
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let y = i64::from(year) - i64::from(month <= 2);
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The date `days` days from 1970-01-01, as year, month and day.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = ((mp + 2) % 12 + 1) as u8;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+impl HttpDate {
+    pub const MIN: HttpDate = HttpDate {
+        year: 0,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    pub const MAX: HttpDate = HttpDate {
+        year: 9999,
+        month: 12,
+        day: 31,
+        hour: 23,
+        minute: 59,
+        second: 59,
+    };
+
+    /// The given date and time, or `None` if it does not exist.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = year <= 9999
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        valid.then_some(HttpDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// From 1 for January to 12 for December.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// From 1 for Monday to 7 for Sunday.
+    pub fn weekday(&self) -> u8 {
+        // 1970-01-01 was a Thursday.
+        ((days_from_civil(self.year, self.month, self.day) + 3).rem_euclid(7) + 1) as u8
+    }
+
+    /// The date `secs` seconds after the Unix epoch, or `None` if it is
+    /// outside [`HttpDate::MIN`] to [`HttpDate::MAX`].
+    pub fn from_unix_secs(secs: i64) -> Option<Self> {
+        if secs < HttpDate::MIN.unix_secs() || secs > HttpDate::MAX.unix_secs() {
+            return None;
+        }
+        let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+        let time = secs.rem_euclid(86400);
+        Some(HttpDate {
+            year: year as u16,
+            month,
+            day,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        })
+    }
+
+    /// Like [`HttpDate::from_unix_secs`], saturating at [`HttpDate::MIN`] and
+    /// [`HttpDate::MAX`].
+    pub fn from_unix_secs_saturating(secs: i64) -> Self {
+        let secs = secs.clamp(HttpDate::MIN.unix_secs(), HttpDate::MAX.unix_secs());
+        HttpDate::from_unix_secs(secs).unwrap_or(HttpDate::MAX)
+    }
+
+    /// The date `duration` later, truncated to whole seconds and saturating
+    /// at [`HttpDate::MAX`].
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        let secs = i64::try_from(duration.as_secs()).unwrap_or(i64::MAX);
+        HttpDate::from_unix_secs_saturating(self.unix_secs().saturating_add(secs))
+    }
+
+    /// Seconds since the Unix epoch, negative before it.
+    pub fn unix_secs(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 86400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+    }
+
+    /// Parses a date in any of the three formats, resolving two-digit years
+    /// against the current year, or [`NO_CLOCK_YEAR`] without `std`.
+    pub fn parse(i: &[u8]) -> Result<Self, ParseError> {
+        parse_date(i, ParseMode::Strict, current_year).map(|(date, _)| date)
+    }
+
+    /// Like [`HttpDate::parse`], resolving two-digit years against `year`,
+    /// e.g. the current year read from a clock available without `std`.
+    pub fn parse_with_year(i: &[u8], year: u16) -> Result<Self, ParseError> {
+        parse_date(i, ParseMode::Strict, || year).map(|(date, _)| date)
+    }
+
+    /// The date as an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+    pub fn to_imf_fixdate(&self) -> [u8; 29] {
+        let put2 = |out: &mut [u8], v: u8| {
+            out[0] = b'0' + v / 10;
+            out[1] = b'0' + v % 10;
+        };
+        let mut ret = *b"Thu, 01 Jan 1970 00:00:00 GMT";
+        ret[..3].copy_from_slice(DAY_NAMES[usize::from(self.weekday()) - 1]);
+        put2(&mut ret[5..7], self.day);
+        ret[8..11].copy_from_slice(MONTHS[usize::from(self.month) - 1]);
+        put2(&mut ret[12..14], (self.year / 100) as u8);
+        put2(&mut ret[14..16], (self.year % 100) as u8);
+        put2(&mut ret[17..19], self.hour);
+        put2(&mut ret[20..22], self.minute);
+        put2(&mut ret[23..25], self.second);
+        ret
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn current_year() -> u16 {
+    HttpDate::from(std::time::SystemTime::now()).year
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn current_year() -> u16 {
+    NO_CLOCK_YEAR
+}
+
+#[derive(Logos, Debug, PartialEq)]
+enum DayNameToken {
+    #[token(b"Mon, ", |_| 1, ignore(ascii_case))]
+    #[token(b"Tue, ", |_| 2, ignore(ascii_case))]
+    #[token(b"Wed, ", |_| 3, ignore(ascii_case))]
+    #[token(b"Thu, ", |_| 4, ignore(ascii_case))]
+    #[token(b"Fri, ", |_| 5, ignore(ascii_case))]
+    #[token(b"Sat, ", |_| 6, ignore(ascii_case))]
+    #[token(b"Sun, ", |_| 7, ignore(ascii_case))]
+    Imf(u8),
+
+    #[token(b"Monday, ", |_| 1, ignore(ascii_case))]
+    #[token(b"Tuesday, ", |_| 2, ignore(ascii_case))]
+    #[token(b"Wednesday, ", |_| 3, ignore(ascii_case))]
+    #[token(b"Thursday, ", |_| 4, ignore(ascii_case))]
+    #[token(b"Friday, ", |_| 5, ignore(ascii_case))]
+    #[token(b"Saturday, ", |_| 6, ignore(ascii_case))]
+    #[token(b"Sunday, ", |_| 7, ignore(ascii_case))]
+    Rfc(u8),
+
+    #[token(b"Mon ", |_| 1, ignore(ascii_case))]
+    #[token(b"Tue ", |_| 2, ignore(ascii_case))]
+    #[token(b"Wed ", |_| 3, ignore(ascii_case))]
+    #[token(b"Thu ", |_| 4, ignore(ascii_case))]
+    #[token(b"Fri ", |_| 5, ignore(ascii_case))]
+    #[token(b"Sat ", |_| 6, ignore(ascii_case))]
+    #[token(b"Sun ", |_| 7, ignore(ascii_case))]
+    Asc(u8),
+}
+
+#[derive(Logos, Debug, PartialEq)]
+enum DateToken {
+    #[token(b" ")]
+    Space,
+    #[token(b"-")]
+    Minus,
+    #[token(b":")]
+    Colon,
+    #[regex(b"[0-9]", |l| l.slice()[0] - b'0')]
+    Digit1(u8),
+    #[regex(b"[0-9][0-9]", |l| parse_u8::<false>(l.slice()).ok())]
+    Digit2(u8),
+    #[regex(b"[0-9][0-9][0-9][0-9]", |l| parse_u16::<false>(l.slice()).ok())]
+    Digit4(u16),
+    #[token(b"Jan", |_| 1, ignore(ascii_case))]
+    #[token(b"Feb", |_| 2, ignore(ascii_case))]
+    #[token(b"Mar", |_| 3, ignore(ascii_case))]
+    #[token(b"Apr", |_| 4, ignore(ascii_case))]
+    #[token(b"May", |_| 5, ignore(ascii_case))]
+    #[token(b"Jun", |_| 6, ignore(ascii_case))]
+    #[token(b"Jul", |_| 7, ignore(ascii_case))]
+    #[token(b"Aug", |_| 8, ignore(ascii_case))]
+    #[token(b"Sep", |_| 9, ignore(ascii_case))]
+    #[token(b"Oct", |_| 10, ignore(ascii_case))]
+    #[token(b"Nov", |_| 11, ignore(ascii_case))]
+    #[token(b"Dec", |_| 12, ignore(ascii_case))]
+    Mon(u8),
+    #[token(b"GMT", ignore(ascii_case))]
+    Gmt,
+    #[token(b"UTC", ignore(ascii_case))]
+    Utc,
+}
+
+fn invalid(offset: usize) -> ParseError {
+    ParseError::new(ParseErrorKind::InvalidDate, offset)
+}
+
+/// Reads the next token, which must match `$p`, evaluating to `$v`.
+macro_rules! expect {
+    ($l:expr, $p:pat => $v:expr) => {
+        match $l.next() {
+            Some(Ok($p)) => $v,
+            _ => return Err(invalid($l.span().start)),
+        }
+    };
+    ($l:expr, $p:pat) => {
+        expect!($l, $p => ())
+    };
+}
+
+/// A component of the date and the offset it starts at.
+type Part<T> = (T, usize);
+
+fn time_of_day(l: &mut Lexer<DateToken>) -> Result<[Part<u8>; 3], ParseError> {
+    let hour = expect!(l, DateToken::Digit2(v) => (v, l.span().start));
+    expect!(l, DateToken::Colon);
+    let minute = expect!(l, DateToken::Digit2(v) => (v, l.span().start));
+    expect!(l, DateToken::Colon);
+    let second = expect!(l, DateToken::Digit2(v) => (v, l.span().start));
+    Ok([hour, minute, second])
+}
+
+/// A two-digit day, or with `compat` also a one-digit one.
+fn day_of_month(l: &mut Lexer<DateToken>, compat: bool) -> Result<Part<u8>, ParseError> {
+    match l.next() {
+        Some(Ok(DateToken::Digit2(v))) => Ok((v, l.span().start)),
+        Some(Ok(DateToken::Digit1(v))) if compat => Ok((v, l.span().start)),
+        _ => Err(invalid(l.span().start)),
+    }
+}
+
+/// `GMT` in any case, or with `compat` also `UTC`.
+fn gmt(l: &mut Lexer<DateToken>, compat: bool) -> Result<(), ParseError> {
+    match l.next() {
+        Some(Ok(DateToken::Gmt)) => Ok(()),
+        Some(Ok(DateToken::Utc)) if compat => Ok(()),
+        _ => Err(invalid(l.span().start)),
+    }
+}
+
+/// The latest year ending in `yy` that is at most 50 years after
+/// `reference_year`.
+fn resolve_two_digit_year(yy: u8, reference_year: u16) -> Option<u16> {
+    let latest = reference_year.saturating_add(50);
+    latest.checked_sub((latest % 100 + 100 - u16::from(yy)) % 100)
+}
+
+/// Parses an HTTP-date in any of its three formats, resolving a two-digit
+/// rfc850-date year against `reference_year`, normally the current year.
+///
+/// The day name must be the actual weekday of the date.
+pub fn parse_http_date(
+    i: &[u8],
+    reference_year: u16,
+) -> Result<(HttpDate, DateFormat), ParseError> {
+    parse_http_date_with(i, ParseMode::Strict, reference_year)
+}
+
+/// Like [`parse_http_date`], following the given [`ParseMode`].
+///
+/// [`ParseMode::Lenient`] matches day names, months and `GMT` without regard
+/// to case, as Section 4.2 of RFC9111 recommends for caches.
+/// [`ParseMode::Compat`] also accepts, like browsers do, a one-digit day, a
+/// four-digit year in an rfc850-date, `UTC` instead of `GMT`, and a day name
+/// that is not the weekday of the date.
+pub fn parse_http_date_with(
+    i: &[u8],
+    mode: ParseMode,
+    reference_year: u16,
+) -> Result<(HttpDate, DateFormat), ParseError> {
+    parse_date(i, mode, || reference_year)
+}
+
+/// Like [`parse_http_date_with`], only calling `reference_year` for an
+/// rfc850-date, so that other dates do not need to read the clock.
+pub(crate) fn parse_date(
+    i: &[u8],
+    mode: ParseMode,
+    reference_year: impl FnOnce() -> u16,
+) -> Result<(HttpDate, DateFormat), ParseError> {
+    use DateToken::*;
+
+    let compat = mode == ParseMode::Compat;
+    // The offset of the first name not capitalized like the ABNF requires.
+    let mut wrong_case = None;
+    let mut check_case = |slice: &[u8], start: usize, expected: &[u8]| {
+        if wrong_case.is_none() && !slice.starts_with(expected) {
+            wrong_case = Some(start);
+        }
+    };
+
+    let mut l = DayNameToken::lexer(i);
+    let day_name = match l.next() {
+        Some(Ok(t)) => t,
+        _ => return Err(invalid(0)),
+    };
+    let (DayNameToken::Imf(w) | DayNameToken::Asc(w) | DayNameToken::Rfc(w)) = day_name;
+    let expected = match day_name {
+        DayNameToken::Rfc(_) => LONG_DAY_NAMES[usize::from(w) - 1],
+        _ => DAY_NAMES[usize::from(w) - 1],
+    };
+    check_case(l.slice(), 0, expected);
+    let mut l = l.morph::<DateToken>();
+    let mut read_month = |l: &mut Lexer<DateToken>| -> Result<u8, ParseError> {
+        let v = expect!(l, Mon(v) => v);
+        check_case(l.slice(), l.span().start, MONTHS[usize::from(v) - 1]);
+        Ok(v)
+    };
+    let (format, year, month, day, time);
+    match day_name {
+        DayNameToken::Imf(_) => {
+            format = DateFormat::ImfFixdate;
+            day = day_of_month(&mut l, compat)?;
+            expect!(l, Space);
+            month = read_month(&mut l)?;
+            expect!(l, Space);
+            year = expect!(l, Digit4(v) => v);
+            expect!(l, Space);
+            time = time_of_day(&mut l)?;
+            expect!(l, Space);
+            gmt(&mut l, compat)?;
+            check_case(l.slice(), l.span().start, b"GMT");
+        }
+        DayNameToken::Rfc(_) => {
+            format = DateFormat::Rfc850;
+            day = day_of_month(&mut l, compat)?;
+            expect!(l, Minus);
+            month = read_month(&mut l)?;
+            expect!(l, Minus);
+            year = match l.next() {
+                Some(Ok(Digit2(v))) => resolve_two_digit_year(v, reference_year())
+                    .ok_or_else(|| invalid(l.span().start))?,
+                Some(Ok(Digit4(v))) if compat => v,
+                _ => return Err(invalid(l.span().start)),
+            };
+            expect!(l, Space);
+            time = time_of_day(&mut l)?;
+            expect!(l, Space);
+            gmt(&mut l, compat)?;
+            check_case(l.slice(), l.span().start, b"GMT");
+        }
+        DayNameToken::Asc(_) => {
+            format = DateFormat::Asctime;
+            month = read_month(&mut l)?;
+            expect!(l, Space);
+            day = match l.next() {
+                Some(Ok(Digit2(v))) => (v, l.span().start),
+                Some(Ok(Space)) => expect!(l, Digit1(v) => (v, l.span().start)),
+                Some(Ok(Digit1(v))) if compat => (v, l.span().start),
+                _ => return Err(invalid(l.span().start)),
+            };
+            expect!(l, Space);
+            time = time_of_day(&mut l)?;
+            expect!(l, Space);
+            year = expect!(l, Digit4(v) => v);
+        }
+    }
+    if l.next().is_some() {
+        return Err(invalid(l.span().start));
+    }
+    if let (ParseMode::Strict, Some(offset)) = (mode, wrong_case) {
+        return Err(invalid(offset));
+    }
+
+    let [(hour, hour_at), (minute, minute_at), (mut second, second_at)] = time;
+    if day.0 == 0 || day.0 > days_in_month(year, month) {
+        return Err(invalid(day.1));
+    }
+    if hour > 23 {
+        return Err(invalid(hour_at));
+    }
+    if minute > 59 {
+        return Err(invalid(minute_at));
+    }
+    if second == 60 && hour == 23 && minute == 59 {
+        second = 59;
+    }
+    let date = HttpDate::new(year, month, day.0, hour, minute, second)
+        .ok_or_else(|| invalid(second_at))?;
+    if date.weekday() != w && !compat {
+        return Err(invalid(0));
+    }
+    Ok((date, format))
+}
+
+impl core::str::FromStr for HttpDate {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HttpDate::parse(s.as_bytes())
+    }
+}
+
+impl TryFrom<&[u8]> for HttpDate {
+    type Error = ParseError;
+
+    fn try_from(i: &[u8]) -> Result<Self, Self::Error> {
+        HttpDate::parse(i)
+    }
+}
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = self.to_imf_fixdate();
+        f.write_str(core::str::from_utf8(&s).map_err(|_| fmt::Error)?)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<HttpDate> for std::time::SystemTime {
+    fn from(date: HttpDate) -> Self {
+        let secs = date.unix_secs();
+        let since_epoch = std::time::Duration::from_secs(secs.unsigned_abs());
+        if secs >= 0 {
+            std::time::UNIX_EPOCH + since_epoch
+        } else {
+            std::time::UNIX_EPOCH - since_epoch
+        }
+    }
+}
+
+/// Truncates to whole seconds, saturating at [`HttpDate::MIN`] and
+/// [`HttpDate::MAX`].
+#[cfg(feature = "std")]
+impl From<std::time::SystemTime> for HttpDate {
+    fn from(time: std::time::SystemTime) -> Self {
+        let secs = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_secs()).unwrap_or(i64::MAX),
+            Err(e) => {
+                let d = e.duration();
+                let secs = d.as_secs().saturating_add(u64::from(d.subsec_nanos() > 0));
+                i64::try_from(secs).map_or(i64::MIN, |s| -s)
+            }
+        };
+        HttpDate::from_unix_secs_saturating(secs)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<HttpDate> for chrono::DateTime<chrono::Utc> {
+    fn from(date: HttpDate) -> Self {
+        chrono::DateTime::from_timestamp(date.unix_secs(), 0).unwrap_or_default()
+    }
+}
+
+/// Truncates to whole seconds, saturating at [`HttpDate::MIN`] and
+/// [`HttpDate::MAX`].
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for HttpDate {
+    fn from(time: chrono::DateTime<Tz>) -> Self {
+        HttpDate::from_unix_secs_saturating(time.timestamp())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<HttpDate> for time::OffsetDateTime {
+    fn from(date: HttpDate) -> Self {
+        time::OffsetDateTime::from_unix_timestamp(date.unix_secs())
+            .unwrap_or(time::OffsetDateTime::UNIX_EPOCH)
+    }
+}
+
+/// Truncates to whole seconds, saturating at [`HttpDate::MIN`] and
+/// [`HttpDate::MAX`].
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for HttpDate {
+    fn from(time: time::OffsetDateTime) -> Self {
+        HttpDate::from_unix_secs_saturating(time.unix_timestamp())
+    }
+}
+
+/// Saturates at `Timestamp::MAX`, which is early on 9999-12-31.
+#[cfg(feature = "jiff")]
+impl From<HttpDate> for jiff::Timestamp {
+    fn from(date: HttpDate) -> Self {
+        jiff::Timestamp::from_second(date.unix_secs()).unwrap_or(jiff::Timestamp::MAX)
+    }
+}
+
+/// Truncates to whole seconds, saturating at [`HttpDate::MIN`] and
+/// [`HttpDate::MAX`].
+#[cfg(feature = "jiff")]
+impl From<jiff::Timestamp> for HttpDate {
+    fn from(time: jiff::Timestamp) -> Self {
+        let secs = time.as_second() - i64::from(time.subsec_nanosecond() < 0);
+        HttpDate::from_unix_secs_saturating(secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| {
+            parse_http_date(s.as_bytes(), 2025)
+                .map(|(d, f)| (d.to_string(), f))
+                .map_err(|e| e.offset())
+        };
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT".to_owned();
+
+        assert_eq!(
+            parse("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Ok((date.clone(), DateFormat::ImfFixdate))
+        );
+        assert_eq!(
+            parse("Sunday, 06-Nov-94 08:49:37 GMT"),
+            Ok((date.clone(), DateFormat::Rfc850))
+        );
+        assert_eq!(
+            parse("Sun Nov  6 08:49:37 1994"),
+            Ok((date, DateFormat::Asctime))
+        );
+        assert_eq!(
+            parse("Wed Dec 31 23:59:59 1969").unwrap().0,
+            "Wed, 31 Dec 1969 23:59:59 GMT"
+        );
+        assert_eq!(
+            parse("Thu Feb 29 12:00:00 2024").unwrap().0,
+            "Thu, 29 Feb 2024 12:00:00 GMT"
+        );
+
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37"), Err(25));
+        assert_eq!(parse("Sun 06 Nov 1994 08:49:37 GMT"), Err(4));
+        assert_eq!(parse("Win, 06 Nov 1994 08:49:37 GMT"), Err(0));
+        assert_eq!(parse("Sun, 06 Now 1994 08:49:37 GMT"), Err(8));
+        assert_eq!(parse("Sun, 6 Nov 1994 08:49:37 GMT"), Err(5));
+        assert_eq!(parse("sun, 06 Nov 1994 08:49:37 GMT"), Err(0));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 GMT "), Err(29));
+        assert_eq!(parse("Sun Nov 6 08:49:37 1994"), Err(8));
+    }
+
+    #[test]
+    fn test_validation() {
+        let parse = |s: &str| parse_http_date(s.as_bytes(), 2025).map_err(|e| e.offset());
+
+        // Weekday must match the date.
+        assert_eq!(parse("Mon, 06 Nov 1994 08:49:37 GMT"), Err(0));
+        assert_eq!(parse("Sun, 31 Nov 1994 08:49:37 GMT"), Err(5));
+        assert_eq!(parse("Fri, 29 Feb 2023 08:49:37 GMT"), Err(5));
+        assert_eq!(parse("Sun, 00 Nov 1994 08:49:37 GMT"), Err(5));
+        assert_eq!(parse("Sun, 06 Nov 1994 25:49:37 GMT"), Err(17));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:60:37 GMT"), Err(20));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:60 GMT"), Err(23));
+
+        // Leap seconds only at the end of a day, read as the second before.
+        let (date, _) = parse("Sat, 31 Dec 2016 23:59:60 GMT").unwrap();
+        assert_eq!(date, HttpDate::new(2016, 12, 31, 23, 59, 59).unwrap());
+    }
+
+    #[test]
+    fn test_two_digit_year() {
+        let year = |s: &str, reference| {
+            parse_http_date(s.as_bytes(), reference)
+                .map(|(d, _)| d.year())
+                .ok()
+        };
+
+        assert_eq!(year("Sunday, 06-Nov-94 08:49:37 GMT", 2025), Some(1994));
+        assert_eq!(year("Wednesday, 06-Nov-75 08:49:37 GMT", 2025), Some(2075));
+        assert_eq!(year("Saturday, 06-Nov-76 08:49:37 GMT", 2025), Some(1976));
+        assert_eq!(year("Thursday, 06-Nov-49 08:49:37 GMT", 2099), Some(2149));
+        // The weekday is checked against the resolved year.
+        assert_eq!(year("Wednesday, 06-Nov-75 08:49:37 GMT", 2020), None);
+        assert_eq!(resolve_two_digit_year(50, 2000), Some(2050));
+        assert_eq!(resolve_two_digit_year(51, 2000), Some(1951));
+        assert_eq!(resolve_two_digit_year(99, 10), None);
+        assert_eq!(year("Sunday, 06-Nov-94 08:49:37 GMT", u16::MAX), None);
+        assert_eq!(resolve_two_digit_year(35, u16::MAX), Some(65535));
+        assert_eq!(resolve_two_digit_year(36, u16::MAX), Some(65436));
+
+        let date = |s: &[u8], year| HttpDate::parse_with_year(s, year).map(|d| d.year());
+        assert_eq!(date(b"Wednesday, 06-Nov-75 08:49:37 GMT", 2025), Ok(2075));
+        assert!(date(b"Wednesday, 06-Nov-75 08:49:37 GMT", 2020).is_err());
+        assert_eq!(date(b"Sun, 06 Nov 1994 08:49:37 GMT", 0), Ok(1994));
+    }
+
+    #[test]
+    fn test_modes() {
+        let parse = |mode, s: &str| {
+            parse_http_date_with(s.as_bytes(), mode, 2025)
+                .map(|(d, _)| d.to_string())
+                .map_err(|e| e.offset())
+        };
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT".to_owned();
+        let all = [ParseMode::Strict, ParseMode::Lenient, ParseMode::Compat];
+
+        for s in [
+            "sun, 06 nov 1994 08:49:37 gmt",
+            "SUNDAY, 06-NOV-94 08:49:37 GMT",
+            "Sun nOV  6 08:49:37 1994",
+        ] {
+            assert!(parse(ParseMode::Strict, s).is_err());
+            assert_eq!(parse(ParseMode::Lenient, s), Ok(date.clone()));
+            assert_eq!(parse(ParseMode::Compat, s), Ok(date.clone()));
+        }
+        assert_eq!(
+            parse(ParseMode::Strict, "Sun, 06 nov 1994 08:49:37 GMT"),
+            Err(8)
+        );
+        assert_eq!(
+            parse(ParseMode::Strict, "Sun, 06 Nov 1994 08:49:37 Gmt"),
+            Err(26)
+        );
+
+        for s in [
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-1994 08:49:37 GMT",
+            "Sunday, 6-Nov-94 08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Mon, 06 Nov 1994 08:49:37 GMT",
+            "Sun Nov 6 08:49:37 1994",
+        ] {
+            assert!(parse(ParseMode::Strict, s).is_err());
+            assert!(parse(ParseMode::Lenient, s).is_err());
+            assert_eq!(parse(ParseMode::Compat, s), Ok(date.clone()));
+        }
+        for mode in all {
+            assert!(parse(mode, "Sun, 06 Nov 1994 08:49:37").is_err());
+            assert!(parse(mode, "Sun, 06 Nov 94 08:49:37 GMT").is_err());
+        }
+    }
+
+    #[test]
+    fn test_reference_year_lazy() {
+        let no_clock = || -> u16 { panic!("reference year read") };
+        assert!(parse_date(
+            b"Sun, 06 Nov 1994 08:49:37 GMT",
+            ParseMode::Strict,
+            no_clock
+        )
+        .is_ok());
+        assert!(parse_date(b"Sun Nov  6 08:49:37 1994", ParseMode::Strict, no_clock).is_ok());
+        assert_eq!(
+            parse_date(b"Sunday, 06-Nov-94 08:49:37 GMT", ParseMode::Strict, || {
+                2025
+            })
+            .map(|(d, _)| d.year()),
+            Ok(1994)
+        );
+    }
+
+    #[test]
+    fn test_unix_secs() {
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(date.unix_secs(), 784111777);
+        assert_eq!(HttpDate::from_unix_secs(784111777), Some(date));
+        assert_eq!(date.weekday(), 7);
+        assert_eq!(HttpDate::from_unix_secs(0).unwrap().weekday(), 4);
+        assert_eq!(
+            HttpDate::from_unix_secs(-1),
+            HttpDate::new(1969, 12, 31, 23, 59, 59)
+        );
+        for date in [HttpDate::MIN, HttpDate::MAX] {
+            assert_eq!(HttpDate::from_unix_secs(date.unix_secs()), Some(date));
+        }
+        assert_eq!(
+            HttpDate::from_unix_secs(HttpDate::MAX.unix_secs() + 1),
+            None
+        );
+        assert_eq!(
+            HttpDate::from_unix_secs(HttpDate::MIN.unix_secs() - 1),
+            None
+        );
+        assert_eq!(HttpDate::new(2023, 2, 29, 0, 0, 0), None);
+        assert_eq!(HttpDate::new(2000, 1, 1, 0, 0, 60), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            &HttpDate::MIN.to_imf_fixdate(),
+            b"Sat, 01 Jan 0000 00:00:00 GMT"
+        );
+        assert_eq!(HttpDate::MAX.to_string(), "Fri, 31 Dec 9999 23:59:59 GMT");
+        assert!(HttpDate::MIN < HttpDate::MAX);
+    }
+
+    #[test]
+    fn test_saturating() {
+        assert_eq!(HttpDate::from_unix_secs_saturating(i64::MIN), HttpDate::MIN);
+        assert_eq!(HttpDate::from_unix_secs_saturating(i64::MAX), HttpDate::MAX);
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(
+            date.saturating_add(Duration::from_millis(86_400_999)),
+            HttpDate::new(1994, 11, 7, 8, 49, 37).unwrap()
+        );
+        assert_eq!(date.saturating_add(Duration::MAX), HttpDate::MAX);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_time() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(SystemTime::from(date), time);
+        assert_eq!(HttpDate::from(time + Duration::from_millis(999)), date);
+        assert_eq!(
+            HttpDate::from(UNIX_EPOCH - Duration::from_millis(500)),
+            HttpDate::new(1969, 12, 31, 23, 59, 59).unwrap()
+        );
+        assert_eq!(
+            HttpDate::from(UNIX_EPOCH + Duration::from_secs(1 << 40)),
+            HttpDate::MAX
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        let time = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        assert_eq!(DateTime::<Utc>::from(date), time);
+        assert_eq!(HttpDate::from(time), date);
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(HttpDate::from(time.with_timezone(&offset)), date);
+        assert_eq!(HttpDate::from(DateTime::<Utc>::MAX_UTC), HttpDate::MAX);
+        assert_eq!(HttpDate::from(DateTime::<Utc>::MIN_UTC), HttpDate::MIN);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        let time = PrimitiveDateTime::new(
+            Date::from_calendar_date(1994, Month::November, 6).unwrap(),
+            Time::from_hms_milli(8, 49, 37, 500).unwrap(),
+        )
+        .assume_utc();
+        assert_eq!(HttpDate::from(time), date);
+        assert_eq!(
+            OffsetDateTime::from(date),
+            time.replace_millisecond(0).unwrap()
+        );
+        assert_eq!(
+            HttpDate::from(OffsetDateTime::from(HttpDate::MIN)),
+            HttpDate::MIN
+        );
+    }
+
+    #[cfg(feature = "jiff")]
+    #[test]
+    fn test_jiff() {
+        use jiff::Timestamp;
+
+        let date = HttpDate::new(1994, 11, 6, 8, 49, 37).unwrap();
+        let time = Timestamp::from_second(784111777).unwrap();
+        assert_eq!(Timestamp::from(date), time);
+        assert_eq!(HttpDate::from(time), date);
+        assert_eq!(
+            HttpDate::from(Timestamp::from_millisecond(-500).unwrap()),
+            HttpDate::new(1969, 12, 31, 23, 59, 59).unwrap()
+        );
+        assert_eq!(Timestamp::from(HttpDate::MAX), Timestamp::MAX);
+        assert_eq!(HttpDate::from(Timestamp::MIN), HttpDate::MIN);
+    }
+}
@@ -0,0 +1,167 @@
+//! Delta seconds
+//!
+//! ### RFC9111 1.2.2. Delta Seconds
+//!
+//! The delta-seconds rule specifies a non-negative integer, representing time
+//! in seconds.
+//!
+//! ```text
+//! delta-seconds  = 1*DIGIT
+//! ```
+//!
+//! A recipient parsing a delta-seconds value and converting it to binary form
+//! ought to use an arithmetic type of at least 31 bits of non-negative integer
+//! range. If a cache receives a delta-seconds value greater than the greatest
+//! integer it can represent, or if any of its subsequent calculations
+//! overflows, the cache MUST consider the value to be 2147483648 (231) or the
+//! greatest positive integer it can conveniently represent.
+//!
+//! Note: The value 2147483648 is here for historical reasons, represents
+//! infinity (over 68 years), and does not need to be stored in binary form; an
+//! implementation could produce it as a string if any overflow occurs, even if
+//! the calculations are performed with an arithmetic type incapable of directly
+//! representing that number. What matters here is that an overflow be detected
+//! and not treated as a negative value in later calculations.
+
+use core::fmt;
+use core::time::Duration;
+
+use crate::diagnostics::{Context, DiagnosticKind};
+use crate::error::ParseError;
+use crate::util::parse_u64;
+
+/// A number of seconds, where anything from 2147483648 (2^31) up is
+/// [`DeltaSeconds::OVERFLOW`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeltaSeconds(u32);
+
+impl DeltaSeconds {
+    pub const ZERO: DeltaSeconds = DeltaSeconds(0);
+
+    /// The value a cache must use when a delta-seconds value or a calculation
+    /// on it overflows, meaning "infinity". It is greater than any other
+    /// value, and encodes as `2147483648`.
+    pub const OVERFLOW: DeltaSeconds = DeltaSeconds(1 << 31);
+
+    /// Saturates at [`DeltaSeconds::OVERFLOW`].
+    pub fn new(seconds: u64) -> Self {
+        DeltaSeconds(seconds.min(Self::OVERFLOW.seconds()) as u32)
+    }
+
+    /// The number of seconds, 2147483648 if overflowed.
+    pub fn seconds(&self) -> u64 {
+        u64::from(self.0)
+    }
+
+    pub fn is_overflow(&self) -> bool {
+        *self == Self::OVERFLOW
+    }
+
+    /// Over 68 years if overflowed.
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_secs(self.seconds())
+    }
+
+    /// Saturates at [`DeltaSeconds::OVERFLOW`].
+    pub fn saturating_add(self, other: DeltaSeconds) -> Self {
+        DeltaSeconds::new(self.seconds() + other.seconds())
+    }
+
+    /// Parses `1*DIGIT`, saturating at [`DeltaSeconds::OVERFLOW`].
+    pub fn parse(i: &[u8]) -> Result<Self, ParseError> {
+        parse_u64::<true>(i).map(DeltaSeconds::new)
+    }
+}
+
+/// Parses a delta-seconds starting at `offset` in the field line, reporting
+/// leading zeros.
+pub(crate) fn process_delta_seconds(
+    ctx: &mut Context,
+    v: &[u8],
+    offset: usize,
+) -> Result<DeltaSeconds, ParseError> {
+    let ret = DeltaSeconds::parse(v).map_err(|e| e.shift(offset))?;
+    if v.len() > 1 && v[0] == b'0' {
+        ctx.warn(DiagnosticKind::LeadingZeros, offset);
+    }
+    Ok(ret)
+}
+
+impl From<u64> for DeltaSeconds {
+    fn from(seconds: u64) -> Self {
+        DeltaSeconds::new(seconds)
+    }
+}
+
+/// Truncates to whole seconds.
+impl From<Duration> for DeltaSeconds {
+    fn from(duration: Duration) -> Self {
+        DeltaSeconds::new(duration.as_secs())
+    }
+}
+
+impl From<DeltaSeconds> for Duration {
+    fn from(delta: DeltaSeconds) -> Self {
+        delta.as_duration()
+    }
+}
+
+impl fmt::Display for DeltaSeconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| DeltaSeconds::parse(s.as_bytes()).map_err(|e| e.kind());
+
+        assert_eq!(parse("0"), Ok(DeltaSeconds::ZERO));
+        assert_eq!(parse("0060"), Ok(DeltaSeconds::new(60)));
+        assert_eq!(parse("2147483647"), Ok(DeltaSeconds::new(2147483647)));
+        assert_eq!(parse("2147483648"), Ok(DeltaSeconds::OVERFLOW));
+        assert_eq!(parse("99999999999999999999999"), Ok(DeltaSeconds::OVERFLOW));
+        assert_eq!(parse(""), Err(ParseErrorKind::InvalidInteger));
+        assert_eq!(parse("-1"), Err(ParseErrorKind::InvalidInteger));
+        assert_eq!(parse("1 "), Err(ParseErrorKind::InvalidInteger));
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert!(!DeltaSeconds::new(2147483647).is_overflow());
+        assert!(DeltaSeconds::new(u64::MAX).is_overflow());
+        assert_eq!(DeltaSeconds::new(u64::MAX).seconds(), 2147483648);
+        assert_eq!(DeltaSeconds::OVERFLOW.to_string(), "2147483648");
+        assert!(DeltaSeconds::new(2147483647) < DeltaSeconds::OVERFLOW);
+        assert_eq!(
+            DeltaSeconds::new(2147483000).saturating_add(DeltaSeconds::new(1000)),
+            DeltaSeconds::OVERFLOW
+        );
+        assert_eq!(
+            DeltaSeconds::OVERFLOW.saturating_add(DeltaSeconds::OVERFLOW),
+            DeltaSeconds::OVERFLOW
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(DeltaSeconds::from(90).to_string(), "90");
+        assert_eq!(
+            DeltaSeconds::from(Duration::from_millis(90_500)),
+            DeltaSeconds::new(90)
+        );
+        assert_eq!(
+            Duration::from(DeltaSeconds::new(90)),
+            Duration::from_secs(90)
+        );
+        assert_eq!(
+            DeltaSeconds::from(Duration::MAX).as_duration(),
+            Duration::from_secs(2147483648)
+        );
+    }
+}
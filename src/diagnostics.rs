@@ -0,0 +1,197 @@
+//! Non-fatal problems found while decoding
+//!
+//! A field can be accepted while still not conforming to its specification,
+//! e.g. when a directive is repeated or an obsolete date format is used. Such
+//! problems are reported to a [`DiagnosticSink`] passed to the `decode_with`
+//! functions of typed headers, without affecting the decoded value.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::ParseErrorKind;
+use crate::mode::ParseMode;
+
+/// What was wrong with a field that was nevertheless accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// The directive appeared more than once; the last occurrence was used.
+    DuplicateDirective(&'static str),
+    /// The directive contradicts another directive present in the field.
+    ConflictingDirectives(&'static str, &'static str),
+    /// An rfc850-date or asctime-date instead of an IMF-fixdate.
+    ObsoleteDateFormat,
+    /// List members or field lines after the first were ignored.
+    DiscardedValues,
+    /// A delta-seconds value with superfluous leading zeros.
+    LeadingZeros,
+    /// An invalid element was ignored instead of failing the whole field.
+    Ignored(ParseErrorKind),
+    /// An invalid value was kept with the meaning the RFC gives it, e.g. an
+    /// Expires date that is treated as already expired.
+    InvalidValue(ParseErrorKind),
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticKind::DuplicateDirective(d) => write!(f, "duplicate `{}` directive", d),
+            DiagnosticKind::ConflictingDirectives(a, b) => {
+                write!(f, "`{}` conflicts with `{}`", a, b)
+            }
+            DiagnosticKind::ObsoleteDateFormat => f.write_str("obsolete HTTP-date format"),
+            DiagnosticKind::DiscardedValues => f.write_str("additional values discarded"),
+            DiagnosticKind::LeadingZeros => f.write_str("delta-seconds with leading zeros"),
+            DiagnosticKind::Ignored(kind) => write!(f, "ignored {}", kind),
+            DiagnosticKind::InvalidValue(kind) => write!(f, "kept {}", kind),
+        }
+    }
+}
+
+/// A non-fatal problem, located like a [`ParseError`](crate::error::ParseError).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    header: &'static str,
+    line: usize,
+    offset: usize,
+    kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind
+    }
+
+    pub fn header(&self) -> &'static str {
+        self.header
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} header: {} (field line {}, byte {})",
+            self.header, self.kind, self.line, self.offset
+        )
+    }
+}
+
+/// Receives diagnostics as they are found.
+///
+/// `()` discards everything, and [`Diagnostics`] collects everything.
+pub trait DiagnosticSink {
+    fn report(&mut self, diagnostic: Diagnostic);
+}
+
+impl DiagnosticSink for () {
+    fn report(&mut self, _diagnostic: Diagnostic) {}
+}
+
+/// Collects every reported diagnostic.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.items
+    }
+}
+
+impl DiagnosticSink for Diagnostics {
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = core::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// Decoding state shared by the parsers of a single field.
+pub(crate) struct Context<'a> {
+    pub(crate) mode: ParseMode,
+    pub(crate) line: usize,
+    header: &'static str,
+    sink: &'a mut dyn DiagnosticSink,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(
+        header: &'static str,
+        mode: ParseMode,
+        sink: &'a mut dyn DiagnosticSink,
+    ) -> Self {
+        Context {
+            mode,
+            line: 0,
+            header,
+            sink,
+        }
+    }
+
+    pub(crate) fn warn(&mut self, kind: DiagnosticKind, offset: usize) {
+        self.sink.report(Diagnostic {
+            header: self.header,
+            line: self.line,
+            offset,
+            kind,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect() {
+        let mut diagnostics = Diagnostics::new();
+        let mut ctx = Context::new("age", ParseMode::Lenient, &mut diagnostics);
+        ctx.line = 2;
+        ctx.warn(DiagnosticKind::LeadingZeros, 3);
+        ctx.warn(DiagnosticKind::DiscardedValues, 5);
+        assert_eq!(diagnostics.len(), 2);
+        let d = diagnostics.iter().next().unwrap();
+        assert_eq!((d.line(), d.offset()), (2, 3));
+        assert_eq!(
+            d.to_string(),
+            "age header: delta-seconds with leading zeros (field line 2, byte 3)"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use core::fmt;
+
+/// Why a header field value was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The field is absent or contains only empty list elements.
+    Empty,
+    /// A byte sequence that does not form any token valid at this position.
+    InvalidToken,
+    /// A well-formed token in a position where it is not allowed.
+    UnexpectedToken,
+    /// A quoted-string without its closing `"`.
+    UnterminatedQuotedString,
+    /// Something other than `1*DIGIT` where an integer was expected.
+    InvalidInteger,
+    /// An integer too large for its type, where saturating is not allowed.
+    IntegerOverflow,
+    /// A directive that requires an argument was given none.
+    MissingArgument,
+    /// A directive that takes no argument was given one.
+    UnexpectedArgument,
+    /// A singleton field was given more than one value.
+    MultipleValues,
+    /// The value is not a valid HTTP-date.
+    InvalidDate,
+    /// The directive is only defined for the other message direction, e.g. a
+    /// response directive in a request.
+    WrongDirection,
+}
+
+impl ParseErrorKind {
+    fn description(&self) -> &'static str {
+        match self {
+            ParseErrorKind::Empty => "empty value",
+            ParseErrorKind::InvalidToken => "invalid token",
+            ParseErrorKind::UnexpectedToken => "unexpected token",
+            ParseErrorKind::UnterminatedQuotedString => "unterminated quoted-string",
+            ParseErrorKind::InvalidInteger => "invalid integer",
+            ParseErrorKind::IntegerOverflow => "integer overflow",
+            ParseErrorKind::MissingArgument => "directive is missing a required argument",
+            ParseErrorKind::UnexpectedArgument => "directive does not take an argument",
+            ParseErrorKind::MultipleValues => "multiple values for a singleton field",
+            ParseErrorKind::InvalidDate => "invalid HTTP-date",
+            ParseErrorKind::WrongDirection => "directive not defined for this message direction",
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// Error returned when a header field cannot be parsed.
+///
+/// Records the lowercase header name, the index of the offending field line
+/// among all lines of that field, and the byte offset within that line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    header: &'static str,
+    line: usize,
+    offset: usize,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        ParseError {
+            header: "",
+            line: 0,
+            offset,
+            kind,
+        }
+    }
+
+    /// Moves the offset, for errors found in a sub-slice of the line.
+    pub(crate) fn shift(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+
+    pub(crate) fn at(mut self, header: &'static str, line: usize) -> Self {
+        self.header = header;
+        self.line = line;
+        self
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    pub fn header(&self) -> &'static str {
+        self.header
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} header: {} (field line {}, byte {})",
+            self.header, self.kind, self.line, self.offset
+        )
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let e = ParseError::new(ParseErrorKind::IntegerOverflow, 4)
+            .shift(8)
+            .at("cache-control", 1);
+        assert_eq!(e.offset(), 12);
+        assert_eq!(
+            e.to_string(),
+            "invalid cache-control header: integer overflow (field line 1, byte 12)"
+        );
+    }
+}
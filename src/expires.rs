@@ -1,61 +1,321 @@
-use headers::{Header, HeaderName, HeaderValue};
-use httpdate::HttpDate;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
 
-#[derive(Clone, Debug, PartialEq)]
-struct Expires(HttpDate);
+use bytes::Bytes;
 
-impl Header for Expires {
-    fn name() -> &'static HeaderName {
-        &http::header::EXPIRES
+use crate::abnf::{is_obs_text, is_vchar, is_wsp};
+use crate::date::{current_year, parse_date, DateFormat, HttpDate};
+use crate::diagnostics::{Context, DiagnosticKind, DiagnosticSink};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::mode::ParseMode;
+
+/// The Expires header field, see Section 5.3 of RFC9111.
+///
+/// A recipient must treat an invalid date, especially `0`, as a time in the
+/// past, so invalid values are kept rather than rejected, and expire before
+/// any valid date.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expires {
+    Valid(HttpDate),
+    Invalid(InvalidValue),
+}
+
+/// The raw field value of an invalid Expires, which can always be encoded
+/// again.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InvalidValue(Bytes);
+
+impl InvalidValue {
+    /// Replaces each byte that cannot appear in a field value, such as CR, LF
+    /// and NUL, with SP, as RFC9110 5.5 allows a recipient to do.
+    pub fn new(raw: &[u8]) -> Self {
+        let valid = |c: u8| is_vchar(c) || is_wsp(c) || is_obs_text(c);
+        if raw.iter().all(|&c| valid(c)) {
+            return InvalidValue(Bytes::copy_from_slice(raw));
+        }
+        let sanitized: Vec<u8> = raw
+            .iter()
+            .map(|&c| if valid(c) { c } else { b' ' })
+            .collect();
+        InvalidValue(sanitized.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Expires {
+    #[cfg(feature = "std")]
+    pub fn new(time: SystemTime) -> Self {
+        Expires::Valid(time.into())
+    }
+
+    /// Expires `duration` after `start`, saturating at [`HttpDate::MAX`].
+    pub fn after(start: impl Into<HttpDate>, duration: Duration) -> Self {
+        Expires::Valid(start.into().saturating_add(duration))
+    }
+
+    /// Expires `duration` from now, saturating at [`HttpDate::MAX`].
+    #[cfg(feature = "std")]
+    pub fn from_now(duration: Duration) -> Self {
+        Expires::after(SystemTime::now(), duration)
+    }
+
+    /// The date, or `None` if the value is invalid.
+    pub fn http_date(&self) -> Option<HttpDate> {
+        match self {
+            Expires::Valid(date) => Some(*date),
+            Expires::Invalid(_) => None,
+        }
     }
 
-    fn decode<'i, I>(values: &mut I) -> Result<Self, headers::Error>
+    /// The date, or [`HttpDate::MIN`] if the value is invalid.
+    pub fn expiration(&self) -> HttpDate {
+        self.http_date().unwrap_or(HttpDate::MIN)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Expires::Valid(_))
+    }
+
+    /// Whether the response has expired at `now`, i.e. the date is not later.
+    /// Always true for an invalid value.
+    pub fn is_past(&self, now: impl Into<HttpDate>) -> bool {
+        self.expiration() <= now.into()
+    }
+}
+
+impl PartialOrd for Expires {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders by expiration, invalid values first and by their raw value.
+impl Ord for Expires {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Expires::Valid(a), Expires::Valid(b)) => a.cmp(b),
+            (Expires::Invalid(a), Expires::Invalid(b)) => a.cmp(b),
+            (Expires::Invalid(_), Expires::Valid(_)) => Ordering::Less,
+            (Expires::Valid(_), Expires::Invalid(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl From<HttpDate> for Expires {
+    fn from(date: HttpDate) -> Self {
+        Expires::Valid(date)
+    }
+}
+
+impl From<Expires> for HttpDate {
+    /// See [`Expires::expiration`].
+    fn from(expires: Expires) -> Self {
+        expires.expiration()
+    }
+}
+
+impl_date_conversions!(Expires);
+
+/// Which value to use when Expires has several field lines.
+///
+/// Only [`ParseMode::Strict`] rejects multiple field lines instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LinePolicy {
+    /// The first valid date, or the first value if none is valid.
+    #[default]
+    FirstValid,
+    /// The first value, even if invalid.
+    First,
+    /// The earliest expiration, i.e. the first invalid value if there is any.
+    Earliest,
+}
+
+fn process_header(
+    ctx: &mut Context,
+    i: &[u8],
+    reference_year: &dyn Fn() -> u16,
+) -> Result<Expires, ParseError> {
+    let (date, format) = parse_date(i, ctx.mode, reference_year)?;
+    if format != DateFormat::ImfFixdate {
+        ctx.warn(DiagnosticKind::ObsoleteDateFormat, 0);
+    }
+    Ok(Expires::Valid(date))
+}
+
+impl Expires {
+    /// Like `headers::Header::decode`, but reports why the value was rejected.
+    ///
+    /// Only a missing value is rejected; an invalid one is
+    /// [`Expires::Invalid`].
+    pub fn try_decode<'i, I, V>(values: &mut I) -> Result<Self, ParseError>
     where
-        I: Iterator<Item = &'i HeaderValue>,
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
     {
-        let h = values.next().ok_or(headers::Error::invalid())?;
-        let ret = Expires(
-            h.to_str()
-                .map_err(|_| headers::Error::invalid())?
-                .parse()
-                .map_err(|_| headers::Error::invalid())?,
-        );
-        if values.next().is_none() {
-            Ok(ret)
-        } else {
-            Err(headers::Error::invalid())
-        }
+        Self::decode_with(values, ParseMode::default(), &mut ())
+    }
+
+    /// Decodes following the given [`ParseMode`], reporting problems that do
+    /// not prevent decoding to `diagnostics`.
+    ///
+    /// [`ParseMode::Strict`] rejects invalid dates and multiple field lines;
+    /// otherwise the first valid one is used. Which dates are valid also
+    /// depends on the mode, see [`parse_http_date_with`].
+    ///
+    /// [`parse_http_date_with`]: crate::date::parse_http_date_with
+    pub fn decode_with<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        Self::decode_with_policy(values, mode, LinePolicy::default(), diagnostics)
     }
 
-    fn encode<E>(&self, values: &mut E)
+    /// Like [`Expires::decode_with`], choosing between multiple field lines
+    /// following `policy`.
+    pub fn decode_with_policy<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        policy: LinePolicy,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
     where
-        E: Extend<HeaderValue>,
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
     {
-        let value = HeaderValue::from_str(&self.0.to_string()).unwrap();
-        values.extend(std::iter::once(value));
+        decode_lines(
+            &mut values.map(|v| v.as_ref()),
+            mode,
+            policy,
+            &current_year,
+            diagnostics,
+        )
+    }
+
+    /// Like [`Expires::decode_with_policy`], resolving two-digit rfc850-date
+    /// years against `reference_year` rather than the current year, e.g.
+    /// read from a clock available without `std`.
+    pub fn decode_with_year<'i, I, V>(
+        values: &mut I,
+        mode: ParseMode,
+        policy: LinePolicy,
+        reference_year: u16,
+        diagnostics: &mut dyn DiagnosticSink,
+    ) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'i V>,
+        V: AsRef<[u8]> + ?Sized + 'i,
+    {
+        decode_lines(
+            &mut values.map(|v| v.as_ref()),
+            mode,
+            policy,
+            &|| reference_year,
+            diagnostics,
+        )
+    }
+
+    /// Like [`Expires::try_decode`], for field line values given as byte
+    /// slices, e.g. read from a log.
+    pub fn parse_field_lines(lines: &[&[u8]]) -> Result<Self, ParseError> {
+        decode_lines(
+            &mut lines.iter().copied(),
+            ParseMode::default(),
+            LinePolicy::default(),
+            &current_year,
+            &mut (),
+        )
+    }
+}
+
+fn decode_lines<'i, I>(
+    values: &mut I,
+    mode: ParseMode,
+    policy: LinePolicy,
+    reference_year: &dyn Fn() -> u16,
+    diagnostics: &mut dyn DiagnosticSink,
+) -> Result<Expires, ParseError>
+where
+    I: Iterator<Item = &'i [u8]>,
+{
+    let mut ctx = Context::new("expires", mode, diagnostics);
+    let mut ret: Option<Expires> = None;
+    for (line, h) in values.enumerate() {
+        ctx.line = line;
+        if let Some(prev) = &ret {
+            if mode == ParseMode::Strict {
+                return Err(ParseError::new(ParseErrorKind::MultipleValues, 0).at("expires", line));
+            }
+            let done = match policy {
+                LinePolicy::FirstValid => prev.is_valid(),
+                LinePolicy::First => true,
+                LinePolicy::Earliest => !prev.is_valid(),
+            };
+            if done {
+                ctx.warn(DiagnosticKind::DiscardedValues, 0);
+                break;
+            }
+        }
+        let value = match process_header(&mut ctx, h, reference_year) {
+            Ok(value) => value,
+            Err(e) if mode == ParseMode::Strict => return Err(e.at("expires", line)),
+            Err(e) => {
+                ctx.warn(DiagnosticKind::InvalidValue(e.kind()), e.offset());
+                Expires::Invalid(InvalidValue::new(h))
+            }
+        };
+        ret = Some(match ret {
+            Some(prev) if policy == LinePolicy::Earliest => prev.min(value),
+            Some(prev) if !value.is_valid() => prev,
+            _ => value,
+        });
+    }
+    ret.ok_or_else(|| ParseError::new(ParseErrorKind::Empty, 0).at("expires", 0))
+}
+
+impl Expires {
+    /// The encoded field lines.
+    pub fn to_field_lines(&self) -> Vec<Bytes> {
+        match self {
+            Expires::Valid(date) => vec![Bytes::copy_from_slice(&date.to_imf_fixdate())],
+            Expires::Invalid(raw) => vec![raw.0.clone()],
+        }
     }
 }
 
+impl_field_value!(Expires);
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use headers::{HeaderMap, HeaderMapExt};
+    use crate::diagnostics::Diagnostics;
 
     #[test]
     fn test_decode() {
-        let decode = |vals| {
-            let mut h = HeaderMap::new();
-            for &val in vals {
-                h.append("Expires", HeaderValue::from_static(val));
-            }
-            h.typed_get::<Expires>()
+        let decode = |mut vals: std::slice::Iter<&str>| {
+            Expires::try_decode(&mut vals)
+                .ok()
+                .and_then(|e| e.http_date())
+                .map(Expires::Valid)
         };
 
-        assert_eq!(decode(["Sun, 06 Nov 1994 08:49:37 GMT"].iter()), Some(Expires("Sun Nov  6 08:49:37 1994".parse().unwrap())));
-        assert_eq!(decode(["Sunday, 06-Nov-94 08:49:37 GMT"].iter()), Some(Expires("Sun Nov  6 08:49:37 1994".parse().unwrap())));
-        assert_eq!(decode(["Sun Nov  6 08:49:37 1994"].iter()), Some(Expires("Sun Nov  6 08:49:37 1994".parse().unwrap())));
-        assert_eq!(decode(["Friday, 06-Nov-70 08:49:37 GMT"].iter()), Some(Expires("Fri Nov  6 08:49:37 1970".parse().unwrap())));
-        assert_eq!(decode(["Wednesday, 06-Nov-69 08:49:37 GMT"].iter()), Some(Expires("Wed Nov  6 08:49:37 2069".parse().unwrap())));
+        assert_eq!(decode(["Sun, 06 Nov 1994 08:49:37 GMT"].iter()), Some(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap())));
+        assert_eq!(decode(["Sunday, 06-Nov-94 08:49:37 GMT"].iter()), Some(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap())));
+        assert_eq!(decode(["Sun Nov  6 08:49:37 1994"].iter()), Some(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap())));
+        assert_eq!(decode(["Thursday, 06-Nov-70 08:49:37 GMT"].iter()), Some(Expires::Valid("Thu Nov  6 08:49:37 2070".parse().unwrap())));
+        assert_eq!(decode(["Friday, 06-Nov-70 08:49:37 GMT"].iter()), None);
+        assert_eq!(decode(["Wednesday, 06-Nov-69 08:49:37 GMT"].iter()), Some(Expires::Valid("Wed Nov  6 08:49:37 2069".parse().unwrap())));
         assert_eq!(decode(["Sun, 06 Nov 1994 08:49:37"].iter()), None);
         assert_eq!(decode(["Sun 06 Nov 1994 08:49:37 GMT"].iter()), None);
         assert_eq!(decode(["Win, 06 Nov 1994 08:49:37 GMT"].iter()), None);
@@ -68,13 +328,227 @@ mod tests {
     }
 
     #[test]
-    fn test_encode() {
-        let encode = |val| {
-            let mut h = HeaderMap::new();
-            h.typed_insert(val);
-            h["expires"].clone()
+    fn test_try_decode() {
+        let try_decode = |vals: &[&'static str]| {
+            Expires::try_decode(&mut vals.iter()).map_err(|e| (e.kind(), e.line()))
+        };
+
+        assert!(try_decode(&["Sun, 06 Nov 1994 08:49:37 GMT"]).is_ok());
+        assert_eq!(try_decode(&[]), Err((ParseErrorKind::Empty, 0)));
+        assert_eq!(
+            try_decode(&["0"]),
+            Ok(Expires::Invalid(InvalidValue::new(b"0")))
+        );
+        assert_eq!(
+            try_decode(&["Sun, 06 Nov 1994 08:49:37 GMT", "0"]),
+            Ok(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap()))
+        );
+        assert_eq!(
+            try_decode(&["0", "Sun, 06 Nov 1994 08:49:37 GMT"]),
+            Ok(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap()))
+        );
+        assert_eq!(
+            try_decode(&["0", "-1"]),
+            Ok(Expires::Invalid(InvalidValue::new(b"0")))
+        );
+    }
+
+    #[test]
+    fn test_decode_with() {
+        let decode = |mode, vals: &[&'static str]| {
+            Expires::decode_with(&mut vals.iter(), mode, &mut ()).map_err(|e| (e.kind(), e.line()))
+        };
+        let date = Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap());
+
+        assert_eq!(
+            decode(ParseMode::Strict, &["0"]),
+            Err((ParseErrorKind::InvalidDate, 0))
+        );
+        let two = [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Mon, 07 Nov 1994 08:49:37 GMT",
+        ];
+        assert_eq!(
+            decode(ParseMode::Strict, &two),
+            Err((ParseErrorKind::MultipleValues, 1))
+        );
+        assert_eq!(decode(ParseMode::Lenient, &two), Ok(date.clone()));
+        assert_eq!(decode(ParseMode::Compat, &two), Ok(date.clone()));
+
+        let invalid_first = ["0", "Sun, 06 Nov 1994 08:49:37 GMT"];
+        assert_eq!(decode(ParseMode::Lenient, &invalid_first), Ok(date.clone()));
+        assert_eq!(decode(ParseMode::Compat, &invalid_first), Ok(date.clone()));
+
+        let lowercase = ["sun, 06 nov 1994 08:49:37 gmt"];
+        assert_eq!(
+            decode(ParseMode::Strict, &lowercase),
+            Err((ParseErrorKind::InvalidDate, 0))
+        );
+        assert_eq!(decode(ParseMode::Lenient, &lowercase), Ok(date.clone()));
+        let browser = ["Sun, 6 Nov 1994 08:49:37 UTC"];
+        assert_eq!(
+            decode(ParseMode::Lenient, &browser),
+            Ok(Expires::Invalid(InvalidValue::new(browser[0].as_bytes())))
+        );
+        assert_eq!(decode(ParseMode::Compat, &browser), Ok(date));
+    }
+
+    #[test]
+    fn test_line_policy() {
+        let decode = |policy, vals: &[&'static str]| {
+            Expires::decode_with_policy(&mut vals.iter(), ParseMode::Lenient, policy, &mut ())
+                .unwrap()
+                .to_string()
         };
+        let vals = [
+            "0",
+            "Mon, 07 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+        ];
+
+        assert_eq!(decode(LinePolicy::First, &vals), "0");
+        assert_eq!(
+            decode(LinePolicy::FirstValid, &vals),
+            "Mon, 07 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(decode(LinePolicy::Earliest, &vals), "0");
+        assert_eq!(
+            decode(LinePolicy::Earliest, &vals[1..]),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(decode(LinePolicy::FirstValid, &["0", "-1"]), "0");
+    }
+
+    #[test]
+    fn test_decode_with_year() {
+        let decode = |mode, year| {
+            let vals = ["Wednesday, 06-Nov-75 08:49:37 GMT"];
+            Expires::decode_with_year(&mut vals.iter(), mode, LinePolicy::default(), year, &mut ())
+                .map(|e| e.to_string())
+                .map_err(|e| e.kind())
+        };
+
+        assert_eq!(
+            decode(ParseMode::Strict, 2025),
+            Ok("Wed, 06 Nov 2075 08:49:37 GMT".to_owned())
+        );
+        // 1975-11-06 was a Thursday.
+        assert_eq!(
+            decode(ParseMode::Lenient, 2020),
+            Ok("Wednesday, 06-Nov-75 08:49:37 GMT".to_owned())
+        );
+        assert_eq!(
+            decode(ParseMode::Strict, 2020),
+            Err(ParseErrorKind::InvalidDate)
+        );
+    }
 
-        assert_eq!(encode(Expires("Sun Nov  6 08:49:37 1994".parse().unwrap())), "Sun, 06 Nov 1994 08:49:37 GMT");
+    #[test]
+    fn test_diagnostics() {
+        let decode = |mode, vals: &[&'static str]| {
+            let mut diagnostics = Diagnostics::new();
+            Expires::decode_with(&mut vals.iter(), mode, &mut diagnostics).unwrap();
+            diagnostics
+                .iter()
+                .map(|d| (d.kind(), d.line()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            decode(ParseMode::Lenient, &["Sun, 06 Nov 1994 08:49:37 GMT"]),
+            []
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, &["Sunday, 06-Nov-94 08:49:37 GMT"]),
+            [(DiagnosticKind::ObsoleteDateFormat, 0)]
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, &["Sun Nov  6 08:49:37 1994", "0"]),
+            [
+                (DiagnosticKind::ObsoleteDateFormat, 0),
+                (DiagnosticKind::DiscardedValues, 1)
+            ]
+        );
+        assert_eq!(
+            decode(ParseMode::Lenient, &["0", "Sun, 06 Nov 1994 08:49:37 GMT"]),
+            [(DiagnosticKind::InvalidValue(ParseErrorKind::InvalidDate), 0)]
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let expires: Expires = date.parse().unwrap();
+        assert_eq!(expires.to_string(), date);
+        assert_eq!(Expires::try_from(date.as_bytes()), Ok(expires.clone()));
+        assert_eq!(
+            Expires::parse_field_lines(&[b"Sunday, 06-Nov-94 08:49:37 GMT"]),
+            Ok(expires)
+        );
+        let invalid: Expires = "0".parse().unwrap();
+        assert_eq!(invalid, Expires::Invalid(InvalidValue::new(b"0")));
+        assert_eq!(invalid.to_string(), "0");
+        assert_eq!(
+            "".parse::<Expires>(),
+            Ok(Expires::Invalid(InvalidValue::new(b"")))
+        );
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let expires = Expires::try_from(&b"a\nb\r\0c\x7f\td\xff"[..]).unwrap();
+        assert_eq!(
+            expires,
+            Expires::Invalid(InvalidValue::new(b"a b  c \td\xff"))
+        );
+        assert_eq!(expires.to_field_lines(), [&b"a b  c \td\xff"[..]]);
+        assert_eq!(InvalidValue::new(b"0").as_bytes(), b"0");
+    }
+
+    #[test]
+    fn test_encode() {
+        let encode = |val: Expires| val.to_field_lines()[0].clone();
+
+        assert_eq!(encode(Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap())), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn test_helpers() {
+        let date: HttpDate = "Sun, 06 Nov 1994 08:49:37 GMT".parse().unwrap();
+        let expires = Expires::after(date, Duration::from_secs(60));
+        assert_eq!(expires.to_string(), "Sun, 06 Nov 1994 08:50:37 GMT");
+        assert!(!expires.is_past(date));
+        assert!(expires.is_past(expires.expiration()));
+        assert_eq!(
+            Expires::after(date, Duration::MAX).http_date(),
+            Some(HttpDate::MAX)
+        );
+
+        let invalid = Expires::Invalid(InvalidValue::new(b"0"));
+        assert_eq!(invalid.http_date(), None);
+        assert_eq!(invalid.expiration(), HttpDate::MIN);
+        assert!(invalid.is_past(HttpDate::MIN));
+        assert!(invalid < expires);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_now() {
+        let expires = Expires::from_now(Duration::from_secs(60));
+        assert!(!expires.is_past(SystemTime::now()));
+        assert!(expires.is_past(SystemTime::now() + Duration::from_secs(61)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_conversions() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+        assert_eq!(
+            Expires::new(time),
+            Expires::Valid("Sun Nov  6 08:49:37 1994".parse().unwrap())
+        );
+        assert_eq!(SystemTime::from(Expires::new(time)), time);
+        let invalid = Expires::Invalid(InvalidValue::new(b""));
+        assert!(SystemTime::from(invalid) < SystemTime::UNIX_EPOCH);
     }
 }
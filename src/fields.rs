@@ -0,0 +1,56 @@
+//! Access to the fields of a message, independent of the `http` version
+//!
+//! Functions that look at several fields of a message, like
+//! [`storability`](crate::storable::storability), read them through the
+//! [`Fields`] trait. It is implemented for the `HeaderMap` of each enabled
+//! `http` version, and for slices of name and value pairs such as those read
+//! from a log.
+
+/// The field section of a message.
+pub trait Fields {
+    /// The values of the field lines named `name`, in order. `name` is
+    /// lowercase.
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a;
+
+    fn contains(&self, name: &str) -> bool {
+        self.values(name).next().is_some()
+    }
+}
+
+impl<N, V> Fields for [(N, V)]
+where
+    N: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.iter()
+            .filter(move |(n, _)| n.as_ref().eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_ref())
+    }
+}
+
+impl<N, V, const M: usize> Fields for [(N, V); M]
+where
+    N: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self[..].values(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let fields = [("Age", "1"), ("date", "x"), ("age", "2")];
+        assert_eq!(
+            fields.values("age").collect::<Vec<_>>(),
+            [&b"1"[..], &b"2"[..]]
+        );
+        assert!(fields.contains("date"));
+        assert!(!fields.contains("expires"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Freshness lifetime calculation
+//!
+//! ### RFC9111 4.2.1. Calculating Freshness Lifetime
+//!
+//! A cache can calculate the freshness lifetime (denoted as
+//! freshness_lifetime) of a response by evaluating the following rules and
+//! using the first match:
+//!
+//! - If the cache is shared and the s-maxage response directive (Section
+//!   5.2.2.10) is present, use its value, or
+//! - If the max-age response directive (Section 5.2.2.1) is present, use its
+//!   value, or
+//! - If the Expires response header field (Section 5.3) is present, use its
+//!   value minus the value of the Date response header field (using the time
+//!   the message was received if it is not present, as per Section 6.6.1 of
+//!   [HTTP]), or
+//! - Otherwise, no explicit expiration time is present in the response. A
+//!   heuristic freshness lifetime might be applicable; see Section 4.2.2.
+//!
+//! Note that this calculation is intended to reduce clock skew by using the
+//! clock information provided by the origin server whenever possible.
+//!
+//! ### RFC9111 4.2.2. Calculating Heuristic Freshness
+//!
+//! Since origin servers do not always provide explicit expiration times, a
+//! cache MAY assign a heuristic expiration time when an explicit time is not
+//! specified, employing algorithms that use other field values (such as the
+//! Last-Modified time) to estimate a plausible expiration time. This
+//! specification does not provide specific algorithms, but it does impose
+//! worst-case constraints on their results.
+//!
+//! A cache MUST NOT use heuristics to determine freshness when an explicit
+//! expiration time is present in the stored response. Because of the
+//! requirements in Section 3, heuristics can only be used on responses
+//! without explicit freshness whose status codes are defined as
+//! "heuristically cacheable" (e.g., see Section 15.1 of [HTTP]) and on
+//! responses without explicit freshness that have been marked as explicitly
+//! cacheable (e.g., with a public response directive).
+//!
+//! If the response has a Last-Modified header field (Section 8.8.2 of
+//! [HTTP]), caches are encouraged to use a heuristic expiration value that is
+//! no more than some fraction of the interval since that time. A typical
+//! setting of this fraction might be 10%.
+
+use std::time::{Duration, SystemTime};
+
+use crate::cache_control::CacheControl;
+use crate::expires::Expires;
+
+/// Whether a cache stores responses for a single user or for many.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    /// A cache shared between users, such as a proxy or a CDN.
+    Shared,
+    /// A cache dedicated to a single user, such as a browser cache.
+    Private,
+}
+
+/// The explicit freshness lifetime of a response, or `None` if the response
+/// carries no explicit expiration time, in which case a [`Heuristic`] may be
+/// used instead.
+///
+/// `date` is the value of the Date header field, and `response_time` the time
+/// the response was received, which is used when Date is missing.
+pub fn freshness_lifetime(
+    kind: CacheKind,
+    cache_control: Option<&CacheControl>,
+    expires: Option<&Expires>,
+    date: Option<SystemTime>,
+    response_time: SystemTime,
+) -> Option<Duration> {
+    if let Some(cc) = cache_control {
+        if let (CacheKind::Shared, Some(s_maxage)) = (kind, cc.s_maxage()) {
+            return Some(s_maxage.as_duration());
+        }
+        if let Some(max_age) = cc.max_age() {
+            return Some(max_age.as_duration());
+        }
+    }
+    let expires = SystemTime::from(expires?.expiration());
+    let date = date.unwrap_or(response_time);
+    Some(expires.duration_since(date).unwrap_or(Duration::ZERO))
+}
+
+/// Whether a status code is heuristically cacheable by default, as listed in
+/// Section 15.1 of RFC9110.
+pub fn is_heuristically_cacheable(status: u16) -> bool {
+    matches!(
+        status,
+        200 | 203 | 204 | 206 | 300 | 301 | 308 | 404 | 405 | 410 | 414 | 501
+    )
+}
+
+/// Estimates a freshness lifetime from Last-Modified, as suggested in
+/// Section 4.2.2 of RFC9111.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Heuristic {
+    fraction: f64,
+    max: Option<Duration>,
+}
+
+impl Default for Heuristic {
+    /// 10% of the time since the last modification, without an upper limit.
+    fn default() -> Self {
+        Heuristic {
+            fraction: 0.1,
+            max: None,
+        }
+    }
+}
+
+impl Heuristic {
+    /// Uses `fraction` of the time since the last modification. A negative or
+    /// NaN `fraction` is taken as 0, and an infinite one as `f64::MAX`.
+    pub fn new(fraction: f64) -> Self {
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, f64::MAX)
+        };
+        Heuristic {
+            fraction,
+            max: None,
+        }
+    }
+
+    /// Never estimates a lifetime longer than `max`.
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// The heuristic freshness lifetime of a response without an explicit
+    /// one, or `None` if heuristics must not be used for it.
+    pub fn lifetime(
+        &self,
+        status: u16,
+        cache_control: Option<&CacheControl>,
+        date: Option<SystemTime>,
+        last_modified: Option<SystemTime>,
+        response_time: SystemTime,
+    ) -> Option<Duration> {
+        let public = cache_control.is_some_and(|cc| cc.public());
+        if !is_heuristically_cacheable(status) && !public {
+            return None;
+        }
+        let date = date.unwrap_or(response_time);
+        let since_modified = date.duration_since(last_modified?).ok()?;
+        // Saturates rather than overflowing for a large fraction.
+        let lifetime = Duration::try_from_secs_f64(since_modified.as_secs_f64() * self.fraction)
+            .unwrap_or(Duration::MAX);
+        Some(self.max.map_or(lifetime, |max| lifetime.min(max)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_freshness_lifetime() {
+        let cc = CacheControl::builder()
+            .max_age(60)
+            .s_maxage(30)
+            .build()
+            .unwrap();
+        let max_age = CacheControl::builder().max_age(60).build().unwrap();
+        let none = CacheControl::default();
+        let expires = Expires::new(at(1_000_100));
+        let invalid: Expires = "0".parse().unwrap();
+        let lifetime =
+            |kind, cc, expires, date| freshness_lifetime(kind, cc, expires, date, at(1_000_010));
+
+        assert_eq!(
+            lifetime(CacheKind::Shared, Some(&cc), None, None),
+            Some(secs(30))
+        );
+        assert_eq!(
+            lifetime(CacheKind::Private, Some(&cc), None, None),
+            Some(secs(60))
+        );
+        assert_eq!(
+            lifetime(CacheKind::Shared, Some(&max_age), Some(&expires), None),
+            Some(secs(60))
+        );
+        assert_eq!(
+            lifetime(
+                CacheKind::Shared,
+                Some(&none),
+                Some(&expires),
+                Some(at(1_000_000))
+            ),
+            Some(secs(100))
+        );
+        assert_eq!(
+            lifetime(CacheKind::Shared, None, Some(&expires), None),
+            Some(secs(90))
+        );
+        assert_eq!(
+            lifetime(CacheKind::Shared, None, Some(&expires), Some(at(2_000_000))),
+            Some(secs(0))
+        );
+        assert_eq!(
+            lifetime(CacheKind::Shared, None, Some(&invalid), None),
+            Some(secs(0))
+        );
+        assert_eq!(lifetime(CacheKind::Shared, Some(&none), None, None), None);
+    }
+
+    #[test]
+    fn test_heuristic() {
+        let public = CacheControl::builder().public().build().unwrap();
+        let h = Heuristic::default();
+        let date = Some(at(1_000_000));
+        let modified = Some(at(0));
+
+        assert_eq!(
+            h.lifetime(200, None, date, modified, at(0)),
+            Some(secs(100_000))
+        );
+        assert_eq!(h.lifetime(201, None, date, modified, at(0)), None);
+        assert_eq!(
+            h.lifetime(201, Some(&public), date, modified, at(0)),
+            Some(secs(100_000))
+        );
+        assert_eq!(h.lifetime(200, None, date, None, at(0)), None);
+        assert_eq!(h.lifetime(200, None, Some(at(0)), date, at(0)), None);
+        assert_eq!(
+            h.max(secs(3600)).lifetime(200, None, date, modified, at(0)),
+            Some(secs(3600))
+        );
+        assert_eq!(
+            Heuristic::new(0.5).lifetime(200, None, None, modified, at(10)),
+            Some(secs(5))
+        );
+        for fraction in [-0.1, f64::NAN, f64::NEG_INFINITY] {
+            assert_eq!(
+                Heuristic::new(fraction).lifetime(200, None, date, modified, at(0)),
+                Some(Duration::ZERO)
+            );
+        }
+        for fraction in [1e300, f64::INFINITY] {
+            assert_eq!(
+                Heuristic::new(fraction).lifetime(200, None, date, modified, at(0)),
+                Some(Duration::MAX)
+            );
+        }
+        assert_eq!(
+            Heuristic::new(f64::INFINITY).lifetime(200, None, date, date, at(0)),
+            Some(Duration::ZERO)
+        );
+    }
+}
@@ -0,0 +1,3 @@
+//! Integration with http 0.2 and headers 0.3
+
+impl_header!(headers_03, http_02);
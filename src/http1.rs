@@ -0,0 +1,3 @@
+//! Integration with http 1.x and headers 0.4
+
+impl_header!(headers_04, http_1);
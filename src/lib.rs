@@ -1,13 +1,47 @@
+//! Typed HTTP caching headers.
+//!
+//! The parsers and typed values only need `alloc`. The `std` feature, on by
+//! default, adds what depends on the system clock or on `std`-only crates:
+//! `SystemTime` conversions, freshness, storability and serving calculations,
+//! and the `headers::Header` impls of the `http02` and `http1` features.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod abnf;
+pub mod date;
+pub mod delta_seconds;
+pub mod diagnostics;
+pub mod error;
+pub mod fields;
+pub mod list;
+pub mod mode;
+pub mod ser;
+#[macro_use]
 pub mod util;
 
 pub mod age;
 pub mod cache_control;
 pub mod expires;
+#[cfg(feature = "std")]
+pub mod freshness;
+#[cfg(feature = "std")]
+pub mod serve;
+#[cfg(feature = "std")]
+pub mod storable;
+
+#[cfg(any(feature = "arbitrary", test))]
+mod arbitrary;
+#[cfg(feature = "http02")]
+mod http02;
+#[cfg(feature = "http1")]
+mod http1;
+#[cfg(feature = "serde")]
+pub mod serde;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     #[test]
     fn it_works() {
         //assert_eq!(Ok((&b""[..], 353u64)), max_age(br#"max-age="3\5\3""#));
@@ -0,0 +1,200 @@
+//! Splitting list-based fields into their elements
+//!
+//! ### RFC9110 5.6.1. Lists (#rule ABNF Extension)
+//!
+//! A construct "#" is defined, similar to "*", for defining comma-delimited
+//! lists of elements. The full form is "<n>#<m>element" indicating at least
+//! <n> and at most <m> elements, each separated by a single comma (",") and
+//! optional whitespace (OWS, defined in Section 5.6.3).
+//!
+//! ### RFC9110 5.6.1.2. Recipient Requirements
+//!
+//! Empty elements do not contribute to the count of elements present. A
+//! recipient MUST parse and ignore a reasonable number of empty list elements:
+//! enough to handle common mistakes by senders that merge values, but not so
+//! much that they could be used as a denial-of-service mechanism.
+//!
+//! ### RFC9110 5.3. Field Order
+//!
+//! A recipient MAY combine multiple field lines within a field section that
+//! have the same field name into one field line, without changing the
+//! semantics of the message, by appending each subsequent field line value to
+//! the initial field line value in order, separated by a comma (",") and
+//! optional whitespace (OWS, defined in Section 5.6.3).
+
+use crate::abnf::is_wsp;
+
+/// A non-empty list element, without surrounding whitespace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Element<'a> {
+    value: &'a [u8],
+    line: usize,
+    offset: usize,
+    source: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    pub fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// The index of the field line the element is in.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The byte offset of the element in its field line.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The field line the element is in, up to the end of the element.
+    pub(crate) fn source(&self) -> &'a [u8] {
+        &self.source[..self.offset + self.value.len()]
+    }
+}
+
+/// Iterates over the elements of a list spanning all the field lines of a
+/// field, skipping empty elements.
+///
+/// Commas inside quoted-strings do not separate elements. An unterminated
+/// quoted-string extends to the end of its field line.
+#[derive(Clone, Debug)]
+pub struct ListIter<'a, I> {
+    values: I,
+    source: &'a [u8],
+    pos: usize,
+    lines: usize,
+}
+
+impl<'a, I> ListIter<'a, I>
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    /// Iterates over the field line values `values`, e.g.
+    /// `values.map(HeaderValue::as_bytes)` for `http::HeaderValue`s.
+    pub fn new(values: I) -> Self {
+        ListIter {
+            values,
+            source: &[],
+            pos: 0,
+            lines: 0,
+        }
+    }
+}
+
+impl<'a> ListIter<'a, core::iter::Empty<&'a [u8]>> {
+    /// Iterates over a single field line, or a list inside a quoted-string.
+    pub fn from_bytes(i: &'a [u8]) -> Self {
+        ListIter {
+            values: core::iter::empty(),
+            source: i,
+            pos: 0,
+            lines: 1,
+        }
+    }
+}
+
+impl<'a, I> ListIter<'a, I> {
+    /// The number of field lines read so far.
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+}
+
+/// The end of the element starting at the beginning of `i`.
+fn element_end(i: &[u8]) -> usize {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (pos, &c) in i.iter().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b',' if !quoted => return pos,
+            _ => {}
+        }
+    }
+    i.len()
+}
+
+impl<'a, I> Iterator for ListIter<'a, I>
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    type Item = Element<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.pos >= self.source.len() {
+                self.source = self.values.next()?;
+                self.pos = 0;
+                self.lines += 1;
+            }
+            let start = self.pos;
+            let rest = &self.source[start..];
+            let raw = &rest[..element_end(rest)];
+            self.pos += raw.len() + 1;
+            let leading = raw.iter().take_while(|&&c| is_wsp(c)).count();
+            let trailing = raw[leading..]
+                .iter()
+                .rev()
+                .take_while(|&&c| is_wsp(c))
+                .count();
+            let value = &raw[leading..raw.len() - trailing];
+            let offset = start + leading;
+            if !value.is_empty() {
+                return Some(Element {
+                    value,
+                    line: self.lines - 1,
+                    offset,
+                    source: self.source,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_iter() {
+        let elements = |vals: &[&'static str]| {
+            let mut list = ListIter::new(vals.iter().map(|v| v.as_bytes()));
+            let ret = list
+                .by_ref()
+                .map(|e| {
+                    let value = std::str::from_utf8(e.value()).unwrap();
+                    format!("{}@{}:{}", value, e.line(), e.offset())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(list.lines(), vals.len());
+            ret
+        };
+
+        assert!(elements(&[]).is_empty());
+        assert!(elements(&["", " , ,,", "\t"]).is_empty());
+        assert_eq!(
+            elements(&["a, b ,c", "", " d e ,"]),
+            ["a@0:0", "b@0:3", "c@0:6", "d e@2:1"]
+        );
+        assert_eq!(
+            elements(&["a=\"x, \\\"y\", b", "c=\"d, e"]),
+            ["a=\"x, \\\"y\"@0:0", "b@0:12", "c=\"d, e@1:0"]
+        );
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let list = ListIter::from_bytes(b" a,, b ");
+        assert_eq!(list.lines(), 1);
+        assert_eq!(
+            list.map(|e| (e.value(), e.offset(), e.source()))
+                .collect::<Vec<_>>(),
+            [(&b"a"[..], 1, &b" a"[..]), (&b"b"[..], 5, &b" a,, b"[..])]
+        );
+        assert_eq!(ListIter::from_bytes(b"").next(), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Parsing strictness
+//!
+//! The RFCs define both the exact grammar a sender must produce and, in many
+//! places, what a recipient should do when a value does not match it. Real
+//! world clients go further still. [`ParseMode`] selects which of these every
+//! typed header follows when decoding.
+
+/// How strictly typed headers follow the RFC grammar when decoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Accept exactly what the RFC ABNF allows, and reject anything else.
+    ///
+    /// Singleton fields must have a single value, and malformed directive
+    /// arguments make the whole field invalid. Intended for conformance
+    /// testing.
+    Strict,
+    /// Follow the recipient-side recommendations of the RFCs.
+    ///
+    /// Use the first member of a list-based singleton field and the first
+    /// valid value of a singleton field, ignore directives with invalid
+    /// arguments, and match the names in HTTP-dates without regard to case.
+    /// Intended for caches and proxies.
+    #[default]
+    Lenient,
+    /// Accept what mainstream browsers accept.
+    ///
+    /// On top of [`ParseMode::Lenient`], integers are read from their leading
+    /// digits, unparseable list members are skipped instead of failing the
+    /// field, and HTTP-dates may deviate from their formats like browsers
+    /// allow, see [`parse_http_date_with`].
+    ///
+    /// [`parse_http_date_with`]: crate::date::parse_http_date_with
+    Compat,
+}
@@ -0,0 +1,233 @@
+//! Serialization of field values
+//!
+//! ### RFC9110 5.6.2. Tokens
+//!
+//! ```text
+//!   token          = 1*tchar
+//!
+//!   tchar          = "!" / "#" / "$" / "%" / "&" / "'" / "*"
+//!                  / "+" / "-" / "." / "^" / "_" / "`" / "|" / "~"
+//!                  / DIGIT / ALPHA
+//!                  ; any VCHAR, except delimiters
+//! ```
+//!
+//! ### RFC9110 5.6.4. Quoted Strings
+//!
+//! ```text
+//!   quoted-string  = DQUOTE *( qdtext / quoted-pair ) DQUOTE
+//!   qdtext         = HTAB / SP / %x21 / %x23-5B / %x5D-7E / obs-text
+//!   quoted-pair    = "\" ( HTAB / SP / VCHAR / obs-text )
+//! ```
+//!
+//! A sender SHOULD NOT generate a quoted-pair in a quoted-string except where
+//! necessary to quote DQUOTE and backslash octets occurring within that
+//! string.
+//!
+//! The writers expect names to be tokens and values to be [`can_quote`], and
+//! check it only with debug assertions. The typed headers reject anything
+//! else when they are built or decoded, so their output always matches the
+//! ABNF.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::abnf::{is_obs_text, is_tchar, is_wsp, quoted_string};
+
+/// Whether `chr` can appear in a quoted-string, possibly escaped.
+pub(crate) fn is_quotable(chr: u8) -> bool {
+    is_wsp(chr) || matches!(chr, 0x21..=0x7e) || is_obs_text(chr)
+}
+
+pub fn is_token(s: &[u8]) -> bool {
+    !s.is_empty() && s.iter().all(|&c| is_tchar(c))
+}
+
+pub fn is_quoted_string(s: &[u8]) -> bool {
+    matches!(quoted_string(s), Ok((rest, _)) if rest.is_empty())
+}
+
+/// Whether `s` can be written as a quoted-string, which excludes control
+/// characters other than HTAB.
+pub fn can_quote(s: &[u8]) -> bool {
+    s.iter().all(|&c| is_quotable(c))
+}
+
+/// Writes `v` as a quoted-string, escaping only DQUOTE and backslash.
+pub fn put_quoted(ret: &mut BytesMut, v: &[u8]) {
+    debug_assert!(can_quote(v), "cannot quote {:?}", v);
+    ret.put_u8(b'"');
+    for &c in v {
+        if c == b'"' || c == b'\\' {
+            ret.put_u8(b'\\');
+        }
+        ret.put_u8(c);
+    }
+    ret.put_u8(b'"');
+}
+
+/// Writes `v` as a token if it is one, and as a quoted-string otherwise.
+pub fn put_token_or_quoted(ret: &mut BytesMut, v: &[u8]) {
+    if is_token(v) {
+        ret.put_slice(v);
+    } else {
+        put_quoted(ret, v);
+    }
+}
+
+/// Builds a comma separated list of `name[=value]` members, optionally with
+/// `;name=value` parameters, as used by Cache-Control and many other fields.
+#[derive(Clone, Debug, Default)]
+pub struct ListWriter {
+    ret: BytesMut,
+}
+
+impl ListWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn put_name(&mut self, name: &[u8]) {
+        debug_assert!(is_token(name), "invalid name {:?}", name);
+        self.ret.put_slice(name);
+    }
+
+    /// Starts a new member named `name`, which must be a token.
+    pub fn member(&mut self, name: impl AsRef<[u8]>) -> &mut Self {
+        if !self.ret.is_empty() {
+            self.ret.put(&b", "[..]);
+        }
+        self.put_name(name.as_ref());
+        self
+    }
+
+    /// Adds `=value` to the current member, as a token if possible.
+    pub fn value(&mut self, value: impl AsRef<[u8]>) -> &mut Self {
+        self.ret.put_u8(b'=');
+        put_token_or_quoted(&mut self.ret, value.as_ref());
+        self
+    }
+
+    /// Adds `=value` to the current member, always as a quoted-string.
+    pub fn quoted(&mut self, value: impl AsRef<[u8]>) -> &mut Self {
+        self.ret.put_u8(b'=');
+        put_quoted(&mut self.ret, value.as_ref());
+        self
+    }
+
+    /// Adds `=value` to the current member, where `value` was already
+    /// serialized. It is kept as is if it is a token or a quoted-string, and
+    /// quoted otherwise.
+    pub fn serialized(&mut self, value: impl AsRef<[u8]>) -> &mut Self {
+        let value = value.as_ref();
+        if is_quoted_string(value) {
+            self.ret.put_u8(b'=');
+            self.ret.put_slice(value);
+            self
+        } else {
+            self.value(value)
+        }
+    }
+
+    /// Adds `=a,b,c` to the current member as a single quoted-string, as
+    /// used for the field names of `no-cache` and `private`.
+    pub fn quoted_list<I, T>(&mut self, items: I) -> &mut Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut list = BytesMut::new();
+        for item in items {
+            if !list.is_empty() {
+                list.put_u8(b',');
+            }
+            list.put_slice(item.as_ref());
+        }
+        self.quoted(list)
+    }
+
+    /// Adds a `;name=value` parameter to the current member.
+    pub fn parameter(&mut self, name: impl AsRef<[u8]>, value: impl AsRef<[u8]>) -> &mut Self {
+        self.ret.put_u8(b';');
+        self.put_name(name.as_ref());
+        self.value(value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ret.is_empty()
+    }
+
+    /// The list as a field value, or `None` if it has no members.
+    pub fn finish(self) -> Option<Bytes> {
+        if self.ret.is_empty() {
+            return None;
+        }
+        Some(self.ret.freeze())
+    }
+}
+
+/// A field value consisting of a single token or quoted-string.
+pub fn single(value: impl AsRef<[u8]>) -> Bytes {
+    let mut ret = BytesMut::new();
+    put_token_or_quoted(&mut ret, value.as_ref());
+    ret.freeze()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoting() {
+        let quote = |v: &[u8]| {
+            let mut ret = BytesMut::new();
+            put_token_or_quoted(&mut ret, v);
+            ret
+        };
+
+        assert_eq!(quote(b"abc"), &b"abc"[..]);
+        assert_eq!(quote(b""), &b"\"\""[..]);
+        assert_eq!(quote(b"a b"), &b"\"a b\""[..]);
+        assert_eq!(quote(b"a\"b\\"), &b"\"a\\\"b\\\\\""[..]);
+        assert_eq!(quote(b"a\tb\xe9"), &b"\"a\tb\xe9\""[..]);
+        assert!(can_quote(b"a\tb\xe9\"\\"));
+        assert!(!can_quote(b"a\r\nb"));
+        assert!(!can_quote(b"\x7f"));
+        assert!(is_quoted_string(&quote(b"a\"b\\")));
+        assert!(is_quoted_string(b"\"\""));
+        assert!(!is_quoted_string(b"\""));
+        assert!(!is_quoted_string(b"\"a\\\""));
+        assert!(!is_quoted_string(b"\"a\"b\""));
+        assert!(!is_quoted_string(b"abc"));
+    }
+
+    #[test]
+    fn test_list_writer() {
+        let mut w = ListWriter::new();
+        assert!(w.is_empty());
+        w.member("max-age").value("60");
+        w.member("private").quoted_list(["a", "b"]);
+        w.member("zak").serialized("\"x\\\"\"");
+        w.member("foo").serialized("a b");
+        w.member("gzip").parameter("q", "0.5").parameter("a", "x y");
+        assert_eq!(
+            w.finish().unwrap(),
+            "max-age=60, private=\"a,b\", zak=\"x\\\"\", foo=\"a b\", \
+             gzip;q=0.5;a=\"x y\""
+        );
+        assert_eq!(ListWriter::new().finish(), None);
+        assert_eq!(single("5"), "5");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invalid name")]
+    fn test_invalid_name() {
+        ListWriter::new().member("bad name");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "cannot quote")]
+    fn test_invalid_quoted() {
+        single("a\r\nb");
+    }
+}
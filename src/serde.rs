@@ -0,0 +1,445 @@
+//! Serde support, enabled with the `serde` feature
+//!
+//! The `Serialize` and `Deserialize` impls of the typed headers use a
+//! structured form, such as a JSON object for `Cache-Control`:
+//!
+//! ```text
+//! {"max_age": 60, "no_cache": ["set-cookie"], "other": [["community", "\"UCI\""]]}
+//! ```
+//!
+//! Only directives that are present are serialized. `no_cache` and `private`
+//! are lists of field names, empty when the directive has no argument, and
+//! `max_stale` is `true` when it has no argument. `other` holds the name and
+//! serialized argument of unknown directives, a string unless it is not
+//! UTF-8, in which case it is an array of bytes. Deserializing accepts the
+//! conflicting directives that decoding accepts, such as `public` with
+//! `private`, but checks the names of unknown directives like
+//! [`CacheControlBuilder::build`].
+//!
+//! `DeltaSeconds` and `Age` are a number of seconds, saturating at
+//! 2147483648. `Expires` is its date as an IMF-fixdate and `RawCacheControl`
+//! its list of field line values.
+//!
+//! The [`wire`] module instead uses the field value as a string, for use with
+//! `#[serde(with = "http_header_types::serde::wire")]`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::age::Age;
+use crate::cache_control::{
+    CacheControl, CacheControlBuilder, RawCacheControl, RequestCacheControl, ResponseCacheControl,
+};
+use crate::delta_seconds::DeltaSeconds;
+use crate::expires::Expires;
+
+/// Uses the field value as a string, through `Display` and `FromStr`.
+pub mod wire {
+    use core::fmt::Display;
+    use core::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s = <alloc::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MaxStale {
+    Any(bool),
+    Seconds(DeltaSeconds),
+}
+
+/// The argument of an unknown directive, kept exactly.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Argument {
+    Utf8(String),
+    Bytes(Vec<u8>),
+}
+
+impl From<&Vec<u8>> for Argument {
+    fn from(argument: &Vec<u8>) -> Self {
+        match String::from_utf8(argument.clone()) {
+            Ok(s) => Argument::Utf8(s),
+            Err(e) => Argument::Bytes(e.into_bytes()),
+        }
+    }
+}
+
+impl From<Argument> for Vec<u8> {
+    fn from(argument: Argument) -> Self {
+        match argument {
+            Argument::Utf8(s) => s.into_bytes(),
+            Argument::Bytes(b) => b,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Directives {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_age: Option<DeltaSeconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_stale: Option<MaxStale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_fresh: Option<DeltaSeconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_cache: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "is_false")]
+    no_store: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    no_transform: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    only_if_cached: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    must_revalidate: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    must_understand: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    transform: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "is_false")]
+    proxy_revalidate: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    public: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s_maxage: Option<DeltaSeconds>,
+    #[serde(skip_serializing_if = "is_false")]
+    immutable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale_while_revalidate: Option<DeltaSeconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale_if_error: Option<DeltaSeconds>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other: Vec<(String, Option<Argument>)>,
+}
+
+impl From<&CacheControl> for Directives {
+    fn from(cc: &CacheControl) -> Self {
+        Directives {
+            max_age: cc.max_age(),
+            max_stale: cc.max_stale().map(|s| match s {
+                None => MaxStale::Any(true),
+                Some(seconds) => MaxStale::Seconds(seconds),
+            }),
+            min_fresh: cc.min_fresh(),
+            no_cache: cc.no_cache().map(<[String]>::to_vec),
+            no_store: cc.no_store(),
+            no_transform: cc.no_transform(),
+            only_if_cached: cc.only_if_cached(),
+            must_revalidate: cc.must_revalidate(),
+            must_understand: cc.must_understand(),
+            transform: cc.transform(),
+            private: cc.private().map(<[String]>::to_vec),
+            proxy_revalidate: cc.proxy_revalidate(),
+            public: cc.public(),
+            s_maxage: cc.s_maxage(),
+            immutable: cc.immutable(),
+            stale_while_revalidate: cc.stale_while_revalidate(),
+            stale_if_error: cc.stale_if_error(),
+            other: cc
+                .other()
+                .iter()
+                .map(|(name, arg)| (name.clone(), arg.as_ref().map(Argument::from)))
+                .collect(),
+        }
+    }
+}
+
+impl Directives {
+    fn builder(self) -> CacheControlBuilder {
+        let mut b = CacheControl::builder();
+        let flags = [
+            (self.no_store, CacheControlBuilder::no_store as fn(_) -> _),
+            (self.no_transform, CacheControlBuilder::no_transform),
+            (self.only_if_cached, CacheControlBuilder::only_if_cached),
+            (self.must_revalidate, CacheControlBuilder::must_revalidate),
+            (self.must_understand, CacheControlBuilder::must_understand),
+            (self.transform, CacheControlBuilder::transform),
+            (self.proxy_revalidate, CacheControlBuilder::proxy_revalidate),
+            (self.public, CacheControlBuilder::public),
+            (self.immutable, CacheControlBuilder::immutable),
+        ];
+        for (set, f) in flags {
+            if set {
+                b = f(b);
+            }
+        }
+        let seconds = [
+            (self.max_age, CacheControlBuilder::max_age as fn(_, _) -> _),
+            (self.min_fresh, CacheControlBuilder::min_fresh),
+            (self.s_maxage, CacheControlBuilder::s_maxage),
+            (
+                self.stale_while_revalidate,
+                CacheControlBuilder::stale_while_revalidate,
+            ),
+            (self.stale_if_error, CacheControlBuilder::stale_if_error),
+        ];
+        for (value, f) in seconds {
+            if let Some(seconds) = value {
+                b = f(b, seconds);
+            }
+        }
+        b = match self.max_stale {
+            None | Some(MaxStale::Any(false)) => b,
+            Some(MaxStale::Any(true)) => b.max_stale_any(),
+            Some(MaxStale::Seconds(seconds)) => b.max_stale(seconds),
+        };
+        b = match self.no_cache {
+            None => b,
+            Some(fields) if fields.is_empty() => b.no_cache(),
+            Some(fields) => b.no_cache_fields(fields),
+        };
+        b = match self.private {
+            None => b,
+            Some(fields) if fields.is_empty() => b.private(),
+            Some(fields) => b.private_fields(fields),
+        };
+        for (name, arg) in self.other {
+            b = b.other(name, arg.map(Vec::from));
+        }
+        b
+    }
+}
+
+impl Serialize for CacheControl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Directives::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CacheControl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Directives::deserialize(deserializer)?
+            .builder()
+            .build_with_conflicts()
+            .map_err(D::Error::custom)
+    }
+}
+
+macro_rules! directional {
+    ($name:ident) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.as_cache_control().serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $name::try_from(CacheControl::deserialize(deserializer)?).map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+directional!(RequestCacheControl);
+directional!(ResponseCacheControl);
+
+impl Serialize for RawCacheControl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            self.to_field_lines()
+                .iter()
+                .map(|line| String::from_utf8_lossy(line)),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for RawCacheControl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let lines = Vec::<String>::deserialize(deserializer)?;
+        RawCacheControl::try_decode(&mut lines.iter().map(String::as_str)).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for DeltaSeconds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.seconds())
+    }
+}
+
+impl<'de> Deserialize<'de> for DeltaSeconds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(DeltaSeconds::new)
+    }
+}
+
+impl Serialize for Age {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.seconds())
+    }
+}
+
+impl<'de> Deserialize<'de> for Age {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(Age::new)
+    }
+}
+
+impl Serialize for Expires {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        wire::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expires {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        wire::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expires::InvalidValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_cache_control() {
+        let cc: CacheControl = "max-age=60, no-cache=\"Set-Cookie\", max-stale, community=\"UCI\""
+            .parse()
+            .unwrap();
+        let value = serde_json::to_value(&cc).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "max_age": 60,
+                "max_stale": true,
+                "no_cache": ["set-cookie"],
+                "other": [["community", "\"UCI\""]],
+            })
+        );
+        assert_eq!(serde_json::from_value::<CacheControl>(value).unwrap(), cc);
+
+        let cc: CacheControl =
+            serde_json::from_value(json!({"private": [], "max_stale": 5, "no_store": false}))
+                .unwrap();
+        assert_eq!(cc.to_string(), "max-stale=5, private");
+        assert!(serde_json::from_value::<CacheControl>(json!({"other": [["a b", null]]})).is_err());
+        // Known directives have their own fields, so the accessors agree with
+        // the encoded form.
+        for other in [json!([["no-store", null]]), json!([["Max-Age", "abc"]])] {
+            assert!(serde_json::from_value::<CacheControl>(json!({ "other": other })).is_err());
+        }
+        assert!(serde_json::from_value::<CacheControl>(json!({"max-age": 5})).is_err());
+        assert!(serde_json::from_value::<RequestCacheControl>(json!({"public": true})).is_err());
+        assert!(serde_json::from_value::<ResponseCacheControl>(json!({"public": true})).is_ok());
+    }
+
+    #[test]
+    fn test_cache_control_as_decoded() {
+        let roundtrip = |cc: &CacheControl| {
+            let value = serde_json::to_value(cc).unwrap();
+            serde_json::from_value::<CacheControl>(value).unwrap()
+        };
+
+        for conflicting in [
+            "public, private",
+            "no-store, immutable",
+            "transform, no-transform",
+        ] {
+            let cc: CacheControl = conflicting.parse().unwrap();
+            assert_eq!(roundtrip(&cc), cc);
+        }
+
+        let cc = CacheControl::parse_field_lines(&[b"a=\"\xff\", b=c"]).unwrap();
+        let value = serde_json::to_value(&cc).unwrap();
+        assert_eq!(value, json!({"other": [["a", [34, 255, 34]], ["b", "c"]]}));
+        assert_eq!(serde_json::from_value::<CacheControl>(value).unwrap(), cc);
+    }
+
+    #[test]
+    fn test_others() {
+        let raw = RawCacheControl::parse_field_lines(&[b"Max-Age=5", b"zak"]).unwrap();
+        let value = serde_json::to_value(&raw).unwrap();
+        assert_eq!(value, json!(["Max-Age=5", "zak"]));
+        assert_eq!(
+            serde_json::from_value::<RawCacheControl>(value)
+                .unwrap()
+                .to_string(),
+            "Max-Age=5, zak"
+        );
+
+        assert_eq!(serde_json::to_value(Age::new(10)).unwrap(), json!(10));
+        assert_eq!(
+            serde_json::from_value::<Age>(json!(10)).unwrap(),
+            Age::new(10)
+        );
+        assert_eq!(
+            serde_json::from_value::<DeltaSeconds>(json!(99999999999u64)).unwrap(),
+            DeltaSeconds::OVERFLOW
+        );
+        assert_eq!(
+            serde_json::to_value(DeltaSeconds::OVERFLOW).unwrap(),
+            json!(2147483648u64)
+        );
+    }
+
+    #[test]
+    fn test_expires() {
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let expires: Expires = date.parse().unwrap();
+        assert_eq!(serde_json::to_value(&expires).unwrap(), json!(date));
+        assert_eq!(
+            serde_json::from_value::<Expires>(json!(date)).unwrap(),
+            expires
+        );
+        assert_eq!(
+            serde_json::from_value::<Expires>(json!("0")).unwrap(),
+            Expires::Invalid(InvalidValue::new(b"0"))
+        );
+    }
+
+    #[test]
+    fn test_wire() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Log {
+            #[serde(with = "wire")]
+            cache_control: CacheControl,
+            #[serde(with = "wire")]
+            age: Age,
+        }
+
+        let log = Log {
+            cache_control: "max-age=60, private=\"set-cookie\"".parse().unwrap(),
+            age: Age::new(5),
+        };
+        let value = serde_json::to_value(&log).unwrap();
+        assert_eq!(
+            value,
+            json!({"cache_control": "max-age=60, private=\"set-cookie\"", "age": "5"})
+        );
+        assert_eq!(serde_json::from_value::<Log>(value).unwrap(), log);
+        assert!(
+            serde_json::from_value::<Log>(json!({"cache_control": "max-age=", "age": "5"}))
+                .is_err()
+        );
+    }
+}
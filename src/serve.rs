@@ -0,0 +1,262 @@
+//! Deciding whether a stored response can satisfy a request
+//!
+//! ### RFC9111 4.2.4. Serving Stale Responses
+//!
+//! A "stale" response is one that either has explicit expiry information or
+//! is allowed to have heuristic expiry calculated, but is not fresh according
+//! to the calculations in Section 4.2.
+//!
+//! A cache MUST NOT generate a stale response if it is prohibited by an
+//! explicit in-protocol directive (e.g., by a no-cache response directive, a
+//! must-revalidate response directive, or an applicable s-maxage or
+//! proxy-revalidate response directive; see Section 5.2.2).
+//!
+//! A cache MUST NOT generate a stale response unless it is disconnected or
+//! doing so is explicitly permitted by the client or origin server (e.g., by
+//! the max-stale request directive in Section 5.2.1, extension directives
+//! such as those defined in [RFC5861], or configuration in accordance with an
+//! out-of-band contract).
+//!
+//! ### RFC9111 5.2.1. Request Directives
+//!
+//! - max-age: the client prefers a response whose age is less than or equal
+//!   to the specified number of seconds.
+//! - max-stale: the client will accept a response that has exceeded its
+//!   freshness lifetime by no more than the specified number of seconds. If
+//!   no value is assigned to max-stale, then the client will accept a stale
+//!   response of any age.
+//! - min-fresh: the client prefers a response whose freshness lifetime is no
+//!   less than its current age plus the specified time in seconds.
+//! - no-cache: the client prefers that a stored response not be used to
+//!   satisfy the request without successful validation on the origin server.
+//! - only-if-cached: the client only wishes to obtain a stored response.
+//!   Caches that honor the only-if-cached request directive SHOULD, upon
+//!   receiving it, respond with either a stored response consistent with the
+//!   other constraints of the request or a 504 (Gateway Timeout) status code.
+
+use std::time::Duration;
+
+use crate::age::Age;
+use crate::cache_control::CacheControl;
+use crate::freshness::CacheKind;
+
+/// What to do with a stored response for a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Decision {
+    /// Serve the stored response, which is fresh.
+    Fresh,
+    /// Serve the stored response, which is stale but acceptable to the client.
+    Stale,
+    /// Validate the stored response with the origin server before using it.
+    Revalidate,
+    /// The client asked for `only-if-cached`, and the stored response cannot
+    /// be used without contacting the origin server; respond with 504
+    /// (Gateway Timeout).
+    GatewayTimeout,
+}
+
+/// Whether a stored response can satisfy a request.
+///
+/// `age` and `freshness_lifetime` are those of the stored response, as given
+/// by [`current_age`](crate::age::current_age) and
+/// [`freshness_lifetime`](crate::freshness::freshness_lifetime) or a
+/// [`Heuristic`](crate::freshness::Heuristic). `request` and `response` are
+/// the Cache-Control fields of the request and of the stored response.
+pub fn decide(
+    kind: CacheKind,
+    request: Option<&CacheControl>,
+    response: Option<&CacheControl>,
+    age: Age,
+    freshness_lifetime: Duration,
+) -> Decision {
+    let decision = decide_online(kind, request, response, age, freshness_lifetime);
+    match decision {
+        Decision::Revalidate if request.is_some_and(|cc| cc.only_if_cached()) => {
+            Decision::GatewayTimeout
+        }
+        decision => decision,
+    }
+}
+
+fn decide_online(
+    kind: CacheKind,
+    request: Option<&CacheControl>,
+    response: Option<&CacheControl>,
+    age: Age,
+    freshness_lifetime: Duration,
+) -> Decision {
+    let default = CacheControl::default();
+    let request = request.unwrap_or(&default);
+    let response = response.unwrap_or(&default);
+    let age = age.seconds();
+    let lifetime = freshness_lifetime.as_secs();
+
+    // A field-name list only requires validation when those fields are sent.
+    let no_cache = |cc: &CacheControl| cc.no_cache().is_some_and(|fields| fields.is_empty());
+    if no_cache(request) || no_cache(response) {
+        return Decision::Revalidate;
+    }
+    if request
+        .max_age()
+        .is_some_and(|max_age| age > max_age.seconds())
+    {
+        return Decision::Revalidate;
+    }
+    // Fresh for exactly `min-fresh` more seconds is fresh enough.
+    if let Some(min_fresh) = request.min_fresh() {
+        if age.saturating_add(min_fresh.seconds()) > lifetime {
+            return Decision::Revalidate;
+        }
+    }
+    if lifetime > age {
+        return Decision::Fresh;
+    }
+
+    // s-maxage also implies the semantics of proxy-revalidate.
+    let shared = kind == CacheKind::Shared;
+    if response.must_revalidate()
+        || (shared && (response.proxy_revalidate() || response.s_maxage().is_some()))
+    {
+        return Decision::Revalidate;
+    }
+    let staleness = age - lifetime;
+    match request.max_stale() {
+        Some(None) => Decision::Stale,
+        Some(Some(max_stale)) if staleness <= max_stale.seconds() => Decision::Stale,
+        _ => Decision::Revalidate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_freshness() {
+        let decide =
+            |age, lifetime| decide(CacheKind::Shared, None, None, Age::new(age), secs(lifetime));
+
+        assert_eq!(decide(10, 60), Decision::Fresh);
+        assert_eq!(decide(60, 60), Decision::Revalidate);
+        assert_eq!(decide(100, 60), Decision::Revalidate);
+    }
+
+    #[test]
+    fn test_request_directives() {
+        let decide = |cc: CacheControl, age, lifetime| {
+            decide(
+                CacheKind::Shared,
+                Some(&cc),
+                None,
+                Age::new(age),
+                secs(lifetime),
+            )
+        };
+        let cc = CacheControl::builder;
+
+        assert_eq!(
+            decide(cc().max_age(5).build().unwrap(), 10, 60),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(cc().max_age(10).build().unwrap(), 10, 60),
+            Decision::Fresh
+        );
+        assert_eq!(
+            decide(cc().min_fresh(51).build().unwrap(), 10, 60),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(cc().min_fresh(50).build().unwrap(), 10, 60),
+            Decision::Fresh
+        );
+        assert_eq!(
+            decide(cc().no_cache().build().unwrap(), 10, 60),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(cc().max_stale(40).build().unwrap(), 100, 60),
+            Decision::Stale
+        );
+        assert_eq!(
+            decide(cc().max_stale(39).build().unwrap(), 100, 60),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(cc().max_stale_any().build().unwrap(), 1000, 60),
+            Decision::Stale
+        );
+        assert_eq!(
+            decide(cc().max_stale_any().max_age(100).build().unwrap(), 1000, 60),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(cc().only_if_cached().build().unwrap(), 10, 60),
+            Decision::Fresh
+        );
+        assert_eq!(
+            decide(cc().only_if_cached().build().unwrap(), 100, 60),
+            Decision::GatewayTimeout
+        );
+        assert_eq!(
+            decide(
+                cc().only_if_cached().max_stale_any().build().unwrap(),
+                100,
+                60
+            ),
+            Decision::Stale
+        );
+    }
+
+    #[test]
+    fn test_response_directives() {
+        let max_stale = CacheControl::builder().max_stale_any().build().unwrap();
+        let decide = |kind, cc: CacheControl, age| {
+            decide(kind, Some(&max_stale), Some(&cc), Age::new(age), secs(60))
+        };
+        let cc = CacheControl::builder;
+        let shared = CacheKind::Shared;
+        let private = CacheKind::Private;
+
+        assert_eq!(
+            decide(shared, cc().no_cache().build().unwrap(), 10),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(
+                shared,
+                cc().no_cache_fields(["set-cookie"]).build().unwrap(),
+                10
+            ),
+            Decision::Fresh
+        );
+        assert_eq!(
+            decide(shared, cc().must_revalidate().build().unwrap(), 10),
+            Decision::Fresh
+        );
+        assert_eq!(
+            decide(private, cc().must_revalidate().build().unwrap(), 100),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(shared, cc().proxy_revalidate().build().unwrap(), 100),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(private, cc().proxy_revalidate().build().unwrap(), 100),
+            Decision::Stale
+        );
+        assert_eq!(
+            decide(shared, cc().s_maxage(60).build().unwrap(), 100),
+            Decision::Revalidate
+        );
+        assert_eq!(
+            decide(private, cc().s_maxage(60).build().unwrap(), 100),
+            Decision::Stale
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Deciding whether a response can be stored
+//!
+//! ### RFC9111 3. Storing Responses in Caches
+//!
+//! A cache MUST NOT store a response to a request unless:
+//!
+//! - the request method is understood by the cache;
+//! - the response status code is final (see Section 15 of [HTTP]);
+//! - if the response status code is 206 or 304, or the must-understand cache
+//!   directive (see Section 5.2.2.3) is present: the cache understands the
+//!   response status code;
+//! - the no-store cache directive is not present in the response (see Section
+//!   5.2.2.5);
+//! - if the cache is shared: the private response directive is either not
+//!   present or allows a shared cache to store a modified response; see
+//!   Section 5.2.2.7);
+//! - if the cache is shared: the Authorization header field is not present in
+//!   the request (see Section 11.6.2 of [HTTP]) or a response directive is
+//!   present that explicitly allows shared caching (see Section 3.5); and
+//! - the response contains at least one of the following:
+//!   - a public response directive (see Section 5.2.2.9);
+//!   - a private response directive, if the cache is not shared (see Section
+//!     5.2.2.7);
+//!   - an Expires header field (see Section 5.3);
+//!   - a max-age response directive (see Section 5.2.2.1);
+//!   - if the cache is shared: an s-maxage response directive (see Section
+//!     5.2.2.10);
+//!   - a cache extension that allows it to be cached (see Section 5.2.3); or
+//!   - a status code that is defined as heuristically cacheable (see Section
+//!     4.2.2).
+//!
+//! In this context, a cache has "understood" a request method or a response
+//! status code if it recognizes it and implements all specified
+//! caching-related behavior.
+//!
+//! ### RFC9111 3.5. Storing Responses to Authenticated Requests
+//!
+//! A shared cache MUST NOT use a cached response to a request with an
+//! Authorization header field (Section 11.6.2 of [HTTP]) to satisfy any
+//! subsequent request unless the response contains a Cache-Control field
+//! with a response directive (Section 5.2.2) that allows it to be stored by
+//! a shared cache, and the cache conforms to the requirements of that
+//! directive for that response.
+//!
+//! In this specification, the following response directives have such an
+//! effect: must-revalidate (Section 5.2.2.2), public (Section 5.2.2.9), and
+//! s-maxage (Section 5.2.2.10).
+//!
+//! ### Understood methods and status codes
+//!
+//! Only GET and HEAD are treated as understood methods. The understood status
+//! codes are the final ones of Section 15 of RFC9110 whose caching behavior
+//! does not depend on other requests, so 206 (Partial Content) and 304 (Not
+//! Modified) are not understood; callers that implement range requests or
+//! revalidation handle those themselves.
+
+use core::fmt;
+
+use crate::cache_control::CacheControl;
+use crate::fields::Fields;
+use crate::freshness::{is_heuristically_cacheable, CacheKind};
+
+/// Why a response must not be stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NotStorable {
+    /// The request method is not understood.
+    Method,
+    /// The status code is informational, so the response is not final.
+    NotFinal,
+    /// The status code is not understood, but had to be.
+    Status,
+    /// The response Cache-Control field could not be parsed.
+    InvalidCacheControl,
+    /// The response has `no-store`.
+    NoStore,
+    /// The response has `private`, and the cache is shared.
+    Private,
+    /// The request has Authorization, the cache is shared, and the response
+    /// does not explicitly allow shared caching.
+    Authorization,
+    /// Nothing in the response allows storing it.
+    NotCacheable,
+}
+
+impl fmt::Display for NotStorable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NotStorable::Method => "request method is not understood",
+            NotStorable::NotFinal => "response is not final",
+            NotStorable::Status => "status code is not understood",
+            NotStorable::InvalidCacheControl => "invalid Cache-Control",
+            NotStorable::NoStore => "response has no-store",
+            NotStorable::Private => "response is private",
+            NotStorable::Authorization => "request has Authorization",
+            NotStorable::NotCacheable => "response is not explicitly cacheable",
+        })
+    }
+}
+
+impl core::error::Error for NotStorable {}
+
+fn is_understood_status(status: u16) -> bool {
+    matches!(
+        status,
+        200 | 203 | 204 | 300 | 301 | 302 | 303 | 307 | 308 | 404 | 405 | 410 | 414 | 501
+    )
+}
+
+/// Whether a cache of the given kind may store the response, and if not,
+/// the first reason it may not.
+///
+/// `method` is the request method, which is case-sensitive, and `status` the
+/// response status code.
+pub fn storability(
+    method: &str,
+    status: u16,
+    request_headers: &(impl Fields + ?Sized),
+    response_headers: &(impl Fields + ?Sized),
+    kind: CacheKind,
+) -> Result<(), NotStorable> {
+    if method != "GET" && method != "HEAD" {
+        return Err(NotStorable::Method);
+    }
+    if (100..200).contains(&status) {
+        return Err(NotStorable::NotFinal);
+    }
+    let cc = match response_headers.contains("cache-control") {
+        true => CacheControl::try_decode(&mut response_headers.values("cache-control"))
+            .map_err(|_| NotStorable::InvalidCacheControl)?,
+        false => CacheControl::default(),
+    };
+    let understood = is_understood_status(status);
+    let must_understand = cc.must_understand() || status == 206 || status == 304;
+    if must_understand && !understood {
+        return Err(NotStorable::Status);
+    }
+    // must-understand overrides no-store for caches that understand the status.
+    if cc.no_store() && !(cc.must_understand() && understood) {
+        return Err(NotStorable::NoStore);
+    }
+    let shared = kind == CacheKind::Shared;
+    // A field-name list allows storing the response without those fields.
+    if shared && cc.private().is_some_and(|fields| fields.is_empty()) {
+        return Err(NotStorable::Private);
+    }
+    if shared
+        && request_headers.contains("authorization")
+        && !(cc.must_revalidate() || cc.public() || cc.s_maxage().is_some())
+    {
+        return Err(NotStorable::Authorization);
+    }
+    let cacheable = cc.public()
+        || (!shared && cc.private().is_some())
+        || response_headers.contains("expires")
+        || cc.max_age().is_some()
+        || (shared && cc.s_maxage().is_some())
+        || is_heuristically_cacheable(status);
+    if !cacheable {
+        return Err(NotStorable::NotCacheable);
+    }
+    Ok(())
+}
+
+/// Whether a cache of the given kind may store the response, as specified in
+/// Section 3 of RFC9111.
+pub fn is_storable(
+    method: &str,
+    status: u16,
+    request_headers: &(impl Fields + ?Sized),
+    response_headers: &(impl Fields + ?Sized),
+    kind: CacheKind,
+) -> bool {
+    storability(method, status, request_headers, response_headers, kind).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storability() {
+        type Pairs<'a> = &'a [(&'a str, &'a str)];
+        let check = |method, status: u16, req: Pairs, resp: Pairs, kind| {
+            storability(method, status, req, resp, kind)
+        };
+        use CacheKind::{Private, Shared};
+
+        assert_eq!(check("GET", 200, &[], &[], Shared), Ok(()));
+        assert_eq!(check("HEAD", 200, &[], &[], Shared), Ok(()));
+        assert_eq!(
+            check("POST", 200, &[], &[], Shared),
+            Err(NotStorable::Method)
+        );
+        assert_eq!(
+            check("GET", 103, &[], &[], Shared),
+            Err(NotStorable::NotFinal)
+        );
+        assert_eq!(
+            check("GET", 206, &[], &[], Private),
+            Err(NotStorable::Status)
+        );
+        assert_eq!(
+            check("GET", 201, &[], &[], Shared),
+            Err(NotStorable::NotCacheable)
+        );
+        assert_eq!(
+            check("GET", 201, &[], &[("cache-control", "max-age=5")], Shared),
+            Ok(())
+        );
+        assert_eq!(check("GET", 201, &[], &[("expires", "0")], Shared), Ok(()));
+        assert_eq!(
+            check("GET", 201, &[], &[("cache-control", "s-maxage=5")], Private),
+            Err(NotStorable::NotCacheable)
+        );
+        assert_eq!(
+            check("GET", 200, &[], &[("cache-control", "no-store")], Private),
+            Err(NotStorable::NoStore)
+        );
+        assert_eq!(
+            check(
+                "GET",
+                200,
+                &[],
+                &[("cache-control", "max-age=\"5")],
+                Private
+            ),
+            Err(NotStorable::InvalidCacheControl)
+        );
+        let must_understand = [("cache-control", "no-store, must-understand")];
+        assert_eq!(check("GET", 200, &[], &must_understand, Shared), Ok(()));
+        assert_eq!(
+            check("GET", 299, &[], &must_understand, Shared),
+            Err(NotStorable::Status)
+        );
+    }
+
+    #[test]
+    fn test_private_and_authorization() {
+        let check = |req: &[_], resp: &[_], kind| is_storable("GET", 200, req, resp, kind);
+        let auth = [("authorization", "Bearer x")];
+
+        assert!(!check(
+            &[],
+            &[("cache-control", "private")],
+            CacheKind::Shared
+        ));
+        assert!(check(
+            &[],
+            &[("cache-control", "private")],
+            CacheKind::Private
+        ));
+        assert!(check(
+            &[],
+            &[("cache-control", "private=\"set-cookie\"")],
+            CacheKind::Shared
+        ));
+        assert!(!check(&auth, &[], CacheKind::Shared));
+        assert!(check(&auth, &[], CacheKind::Private));
+        assert!(check(
+            &auth,
+            &[("cache-control", "public")],
+            CacheKind::Shared
+        ));
+        assert!(check(
+            &auth,
+            &[("cache-control", "s-maxage=0")],
+            CacheKind::Shared
+        ));
+        assert!(check(
+            &auth,
+            &[("cache-control", "must-revalidate")],
+            CacheKind::Shared
+        ));
+    }
+}
@@ -1,36 +1,45 @@
+use crate::error::{ParseError, ParseErrorKind};
+
 // Sorry, Rust doesn't have proper num traits built-in, so it's just easiest to do this with a macro
 macro_rules! parse_generic {
     ($name:ident, $int_type:ty, $safe_digits:expr) => {
-        pub fn $name<const SAT: bool>(s: &[u8]) -> Result<$int_type, ()> {
+        pub fn $name<const SAT: bool>(s: &[u8]) -> Result<$int_type, ParseError> {
+            let invalid = |i| ParseError::new(ParseErrorKind::InvalidInteger, i);
             if s.is_empty() {
-                return Err(());
+                return Err(invalid(0));
             }
             let mut result: $int_type = 0;
-            for digit in s.iter().take($safe_digits) {
+            for (i, digit) in s.iter().enumerate().take($safe_digits) {
                 match digit {
                     b'0'..=b'9' => result = (result * 10) + (digit & 0x0F) as $int_type,
-                    _ => return Err(()),
+                    _ => return Err(invalid(i)),
                 }
             }
-            for digit in s.iter().skip($safe_digits) {
+            // An invalid byte after the point of overflow is still reported as
+            // invalid, as it is when saturating. Otherwise the overflow is
+            // reported at the first digit that caused it.
+            let mut overflowed = None;
+            for (i, digit) in s.iter().enumerate().skip($safe_digits) {
                 match digit {
                     b'0'..=b'9' => {
-                        if SAT {
-                            result = result
-                                .saturating_mul(10)
-                                .saturating_add((digit & 0x0F) as $int_type)
-                        } else {
-                            result = result
-                                .checked_mul(10)
-                                .ok_or(())?
-                                .checked_add((digit & 0x0F) as $int_type)
-                                .ok_or(())?
+                        let next = result
+                            .checked_mul(10)
+                            .and_then(|r| r.checked_add((digit & 0x0F) as $int_type));
+                        match next {
+                            Some(next) => result = next,
+                            None => {
+                                overflowed = overflowed.or(Some(i));
+                                result = <$int_type>::MAX;
+                            }
                         }
                     }
-                    _ => return Err(()),
+                    _ => return Err(invalid(i)),
                 }
             }
-            Ok(result)
+            match overflowed {
+                Some(i) if !SAT => Err(ParseError::new(ParseErrorKind::IntegerOverflow, i)),
+                _ => Ok(result),
+            }
         }
     };
 }
@@ -39,21 +48,199 @@ parse_generic!(parse_u16, u16, 4);
 parse_generic!(parse_u32, u32, 9);
 parse_generic!(parse_u64, u64, 19);
 
+/// The longest prefix of `s` consisting of ASCII digits, for parsers that
+/// ignore trailing garbage after a number.
+pub fn leading_digits(s: &[u8]) -> &[u8] {
+    let len = s.iter().take_while(|c| c.is_ascii_digit()).count();
+    &s[..len]
+}
+
+/// Implements `FromStr`, `TryFrom<&[u8]>` and `Display` for a typed header
+/// with `parse_field_lines` and `to_field_lines`, displaying the encoded field
+/// lines joined like a combined field value.
+macro_rules! impl_field_value {
+    ($name:ty) => {
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::error::ParseError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::parse_field_lines(&[s.as_bytes()])
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = $crate::error::ParseError;
+
+            fn try_from(i: &[u8]) -> Result<Self, Self::Error> {
+                Self::parse_field_lines(&[i])
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                for (i, v) in self.to_field_lines().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(&::alloc::string::String::from_utf8_lossy(v))?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Implements conversions between a date-valued header and `SystemTime` and
+/// the date types of the `chrono`, `time` and `jiff` features, through its
+/// [`HttpDate`](crate::date::HttpDate), which it must convert from and into.
+macro_rules! impl_date_conversions {
+    ($name:ident) => {
+        impl_date_conversions!(@impl $name, "std", std::time::SystemTime);
+        impl_date_conversions!(@impl $name, "chrono", chrono::DateTime<chrono::Utc>);
+        impl_date_conversions!(@impl $name, "time", time::OffsetDateTime);
+        impl_date_conversions!(@impl $name, "jiff", jiff::Timestamp);
+    };
+    (@impl $name:ident, $feature:literal, $ty:ty) => {
+        #[cfg(feature = $feature)]
+        impl From<$ty> for $name {
+            fn from(time: $ty) -> Self {
+                $name::from($crate::date::HttpDate::from(time))
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl From<$name> for $ty {
+            fn from(value: $name) -> Self {
+                $crate::date::HttpDate::from(value).into()
+            }
+        }
+    };
+}
+
+/// Integrates with the given `headers` and `http` crates: implements
+/// `headers::Header` for every typed header and [`Fields`] for
+/// `http::HeaderMap`, along with the tests of both.
+///
+/// [`Fields`]: crate::fields::Fields
+#[allow(unused_macros)]
+macro_rules! impl_header {
+    ($headers:ident, $http:ident) => {
+        impl_header!(
+            @impl $headers,
+            $http,
+            $crate::age::Age => AGE,
+            $crate::cache_control::CacheControl => CACHE_CONTROL,
+            $crate::cache_control::RequestCacheControl => CACHE_CONTROL,
+            $crate::cache_control::ResponseCacheControl => CACHE_CONTROL,
+            $crate::cache_control::RawCacheControl => CACHE_CONTROL,
+            $crate::expires::Expires => EXPIRES,
+        );
+
+        impl $crate::fields::Fields for $http::HeaderMap {
+            fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a [u8]> + 'a {
+                self.get_all(name).iter().map($http::HeaderValue::as_bytes)
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use $crate::age::Age;
+            use $crate::cache_control::{CacheControl, RawCacheControl};
+            use $crate::delta_seconds::DeltaSeconds;
+            use $crate::expires::{Expires, InvalidValue};
+            use $crate::fields::Fields;
+            use $headers::HeaderMapExt;
+
+            #[test]
+            fn test_header() {
+                let mut h = $http::HeaderMap::new();
+                h.append("cache-control", "max-age=60".parse().unwrap());
+                h.append("cache-control", "No-Store".parse().unwrap());
+                h.insert("age", "10".parse().unwrap());
+                let cc = h.typed_get::<CacheControl>().unwrap();
+                assert_eq!(cc.max_age(), Some(DeltaSeconds::new(60)));
+                assert!(cc.no_store());
+                assert_eq!(h.typed_get::<Age>(), Some(Age::new(10)));
+                assert_eq!(h.typed_get::<Expires>(), None);
+                h.insert("expires", "0".parse().unwrap());
+                assert_eq!(
+                    h.typed_get::<Expires>(),
+                    Some(Expires::Invalid(InvalidValue::new(b"0")))
+                );
+                h.typed_insert(Expires::try_from(&b"a\nb"[..]).unwrap());
+                assert_eq!(h["expires"], "a b");
+
+                h.typed_insert(cc);
+                assert_eq!(h["cache-control"], "max-age=60, no-store");
+                h.insert("cache-control", "max-age=".parse().unwrap());
+                assert_eq!(h.typed_get::<CacheControl>(), None);
+                assert!(h.typed_try_get::<RawCacheControl>().is_err());
+            }
+
+            #[test]
+            fn test_fields() {
+                let mut h = $http::HeaderMap::new();
+                h.append("age", "1".parse().unwrap());
+                h.append("Age", "2".parse().unwrap());
+                assert_eq!(
+                    Fields::values(&h, "age").collect::<Vec<_>>(),
+                    [&b"1"[..], &b"2"[..]]
+                );
+                assert!(Fields::contains(&h, "age"));
+                assert!(!Fields::contains(&h, "date"));
+            }
+        }
+    };
+    (@impl $headers:ident, $http:ident, $($name:ty => $field:ident),* $(,)?) => {
+        $(
+            impl $headers::Header for $name {
+                fn name() -> &'static $http::HeaderName {
+                    &$http::header::$field
+                }
+
+                fn decode<'i, I>(values: &mut I) -> Result<Self, $headers::Error>
+                where
+                    I: Iterator<Item = &'i $http::HeaderValue>,
+                {
+                    Self::try_decode(values).map_err(|_| $headers::Error::invalid())
+                }
+
+                fn encode<E>(&self, values: &mut E)
+                where
+                    E: Extend<$http::HeaderValue>,
+                {
+                    values.extend(self.to_field_lines().into_iter().map(|v| {
+                        $http::HeaderValue::from_maybe_shared(v).expect("valid field value")
+                    }));
+                }
+            }
+        )*
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn invalid(offset: usize) -> ParseError {
+        ParseError::new(ParseErrorKind::InvalidInteger, offset)
+    }
+
+    fn overflow(offset: usize) -> ParseError {
+        ParseError::new(ParseErrorKind::IntegerOverflow, offset)
+    }
+
     #[test]
     fn test_parse_u64() {
-        assert_eq!(parse_u64::<false>(b""), Err(()));
-        assert_eq!(parse_u64::<false>(b"+0"), Err(()));
-        assert_eq!(parse_u64::<false>(b"+"), Err(()));
-        assert_eq!(parse_u64::<false>(b"garble0"), Err(()));
-        assert_eq!(parse_u64::<false>(b"0garble"), Err(()));
-        assert_eq!(parse_u64::<false>(b" 0"), Err(()));
-        assert_eq!(parse_u64::<false>(b"0 "), Err(()));
-        assert_eq!(parse_u64::<false>(b"-0"), Err(()));
-        assert_eq!(parse_u64::<false>(b"-1"), Err(()));
+        assert_eq!(parse_u64::<false>(b""), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"+0"), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"+"), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"garble0"), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"0garble"), Err(invalid(1)));
+        assert_eq!(parse_u64::<false>(b" 0"), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"0 "), Err(invalid(1)));
+        assert_eq!(parse_u64::<false>(b"-0"), Err(invalid(0)));
+        assert_eq!(parse_u64::<false>(b"-1"), Err(invalid(0)));
         assert_eq!(parse_u64::<false>(b"0"), Ok(0u64));
         assert_eq!(parse_u64::<false>(b"000000"), Ok(0u64));
         assert_eq!(parse_u64::<false>(b"00000000000000000000"), Ok(0u64));
@@ -64,11 +251,34 @@ mod tests {
             parse_u64::<false>(b"9999999999999999"),
             Ok(9999999999999999u64)
         );
-        assert_eq!(parse_u64::<false>(b"99999999999999999999"), Err(()));
+        assert_eq!(
+            parse_u64::<false>(b"99999999999999999999"),
+            Err(overflow(19))
+        );
+        assert_eq!(parse_u64::<false>(b"18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(
+            parse_u64::<false>(b"184467440737095516160"),
+            Err(overflow(19))
+        );
+        assert_eq!(
+            parse_u64::<true>(b"99999999999999999999x"),
+            Err(invalid(20))
+        );
         assert_eq!(parse_u64::<true>(b"99999999999999999999"), Ok(u64::MAX));
         assert_eq!(parse_u8::<false>(b"99"), Ok(99u8));
         assert_eq!(parse_u8::<false>(b"255"), Ok(255u8));
-        assert_eq!(parse_u8::<false>(b"256"), Err(()));
+        assert_eq!(parse_u8::<false>(b"256"), Err(overflow(2)));
+        assert_eq!(parse_u8::<false>(b"000256"), Err(overflow(5)));
+        assert_eq!(parse_u8::<false>(b"2550"), Err(overflow(3)));
         assert_eq!(parse_u8::<true>(b"256"), Ok(u8::MAX));
+        assert_eq!(parse_u8::<false>(b"301x"), Err(invalid(3)));
+    }
+
+    #[test]
+    fn test_leading_digits() {
+        assert_eq!(leading_digits(b"123abc"), b"123");
+        assert_eq!(leading_digits(b"123"), b"123");
+        assert_eq!(leading_digits(b"abc"), b"");
+        assert_eq!(leading_digits(b""), b"");
     }
 }